@@ -1,10 +1,11 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::str::FromStr;
 
-use crate::error::{Error, Result};
-use headers::{HeaderMap, HeaderName, HeaderValue};
+use crate::error::{Error, Result, Warning};
+use headers::{HeaderMap, HeaderValue};
 
-const HEADER_SEPARATOR: &str = ":";
+const HEADER_SEPARATOR: char = ':';
 
 /// A case-sensitive header map.
 ///
@@ -13,9 +14,18 @@ const HEADER_SEPARATOR: &str = ":";
 ///
 /// This is required because some vendors use the same headers
 /// and the only way to differentiate them is by the case.
+///
+/// The casing of header names is kept when parsing headers from text, from
+/// raw HTTP/1.x response bytes (see [`CaseSensitiveHeaderMap::from_http1`])
+/// or when collecting name/value pairs. An [`http::HeaderMap`] only contains
+/// lowercased names, so maps converted from it do not preserve the casing
+/// and vendors are detected by the header values instead.
+///
+/// A header may occur multiple times. All of its values are kept in order.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CaseSensitiveHeaderMap {
-    inner: HashMap<String, HeaderValue>,
+    inner: HashMap<String, Vec<HeaderValue>>,
+    preserves_case: bool,
 }
 
 impl Default for CaseSensitiveHeaderMap {
@@ -29,52 +39,323 @@ impl CaseSensitiveHeaderMap {
     pub fn new() -> Self {
         Self {
             inner: HashMap::new(),
+            preserves_case: true,
+        }
+    }
+
+    /// Parse the headers of a raw HTTP/1.x response, keeping the casing
+    /// of the header names as sent on the wire.
+    ///
+    /// The status line is skipped if present.
+    /// Parsing stops at the empty line which separates headers and body.
+    /// Use [`ResponseHead`](crate::response::ResponseHead) to parse the
+    /// status code as well.
+    ///
+    /// ```
+    /// use rate_limits::CaseSensitiveHeaderMap;
+    ///
+    /// let raw = b"HTTP/1.1 200 OK\r\nX-RateLimit-Limit: 60\r\n\r\n{}";
+    /// let headers = CaseSensitiveHeaderMap::from_http1(raw).unwrap();
+    /// assert!(headers.get("X-RateLimit-Limit").is_some());
+    /// assert!(headers.get("x-ratelimit-limit").is_none());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if a header line does not contain
+    /// a colon or if a header name or value is invalid.
+    pub fn from_http1(raw: &[u8]) -> Result<Self> {
+        let mut lines = raw_lines(raw).peekable();
+        if lines
+            .peek()
+            .is_some_and(|(_, line)| line.starts_with(b"HTTP/"))
+        {
+            lines.next();
+        }
+        Self::parse_block(&mut lines)
+    }
+
+    /// Parse header lines up to the empty line which ends the header block.
+    ///
+    /// Lines starting with whitespace continue the value of the previous
+    /// header (obsolete line folding) and are joined with a single space.
+    pub(crate) fn parse_block<'a, I>(lines: &mut I) -> Result<Self>
+    where
+        I: Iterator<Item = (usize, &'a [u8])>,
+    {
+        let mut fields: Vec<(usize, Vec<u8>)> = Vec::new();
+        for (number, line) in lines.by_ref() {
+            if line.trim_ascii().is_empty() {
+                break;
+            }
+            match fields.last_mut() {
+                Some((_, field)) if line.starts_with(b" ") || line.starts_with(b"\t") => {
+                    field.push(b' ');
+                    field.extend_from_slice(line.trim_ascii());
+                }
+                _ => fields.push((number, line.trim_ascii_start().to_vec())),
+            }
+        }
+
+        let mut map = Self::new();
+        for (number, field) in fields {
+            map.insert_line(&field)
+                .map_err(|e| Error::InvalidHeaderLine(number, Box::new(e)))?;
         }
+        Ok(map)
     }
 
-    /// Insert a new header.
+    /// Parse a `name: value` line and insert the header,
+    /// keeping the casing of its name
+    fn insert_line(&mut self, line: &[u8]) -> Result<()> {
+        let colon = line
+            .iter()
+            .position(|&b| char::from(b) == HEADER_SEPARATOR)
+            .ok_or_else(|| Error::HeaderWithoutColon(String::from_utf8_lossy(line).into()))?;
+        let (name, value) = (&line[..colon], &line[colon + 1..]);
+        // Validate the name, but keep its original casing
+        headers::HeaderName::from_bytes(name)?;
+        self.append(
+            String::from_utf8_lossy(name).into(),
+            HeaderValue::from_bytes(value.trim_ascii())?,
+        );
+        Ok(())
+    }
+
+    /// Whether the casing of the header names is known.
+    ///
+    /// This is `false` for maps converted from an [`http::HeaderMap`],
+    /// whose header names are always lowercase.
+    #[must_use]
+    pub const fn preserves_case(&self) -> bool {
+        self.preserves_case
+    }
+
+    /// Insert a new header, replacing all previous values.
+    ///
+    /// Returns the first of the previous values.
     pub fn insert(&mut self, name: String, value: HeaderValue) -> Option<HeaderValue> {
-        self.inner.insert(name, value)
+        self.inner
+            .insert(name, vec![value])
+            .and_then(|values| values.into_iter().next())
+    }
+
+    /// Append a value to a header, keeping all previous values.
+    pub fn append(&mut self, name: String, value: HeaderValue) {
+        self.inner.entry(name).or_default().push(value);
     }
 
-    /// Get a header.
+    /// Get the first value of a header.
     pub fn get(&self, k: &str) -> Option<&HeaderValue> {
-        self.inner.get(k)
+        self.get_all(k)?.first()
+    }
+
+    /// Get all values of a header in order.
+    pub fn get_all(&self, k: &str) -> Option<&[HeaderValue]> {
+        self.inner.get(k).map(Vec::as_slice)
+    }
+
+    /// Get the first value of a header, preferring an exact match of the name
+    /// over a case-insensitive one.
+    pub fn get_ignore_case(&self, k: &str) -> Option<&HeaderValue> {
+        self.get(self.names_ignore_case(k).first()?)
+    }
+
+    /// Get all values of a header regardless of the casing of its name.
+    ///
+    /// If the header was sent with several casings, their values are merged.
+    /// The values of an exact match of the name come first, followed by the
+    /// other casings in lexicographic order.
+    pub fn get_all_ignore_case(&self, k: &str) -> Option<Cow<'_, [HeaderValue]>> {
+        match self.names_ignore_case(k).as_slice() {
+            [] => None,
+            [name] => self.get_all(name).map(Cow::Borrowed),
+            names => Some(Cow::Owned(
+                names
+                    .iter()
+                    .flat_map(|name| &self.inner[*name])
+                    .cloned()
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Get all names which equal the given name regardless of their casing.
+    ///
+    /// An exact match comes first, followed by the other casings
+    /// in lexicographic order, so the result does not depend on the order
+    /// of the underlying hash map.
+    fn names_ignore_case(&self, k: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .inner
+            .keys()
+            .map(String::as_str)
+            .filter(|name| name.eq_ignore_ascii_case(k))
+            .collect();
+        names.sort_by_key(|name| (*name != k, *name));
+        names
+    }
+
+    /// Check whether a header was sent with several casings of its name
+    /// and different values.
+    pub(crate) fn conflicting_casings(&self, k: &str) -> Option<Warning> {
+        let names = self.names_ignore_case(k);
+        let (first, rest) = names.split_first()?;
+        if rest
+            .iter()
+            .all(|name| self.inner[*name] == self.inner[*first])
+        {
+            return None;
+        }
+        Some(Warning::ConflictingCasings(
+            k.to_string(),
+            names.iter().map(ToString::to_string).collect(),
+        ))
+    }
+
+    /// Get the values of a list-based header, combined into a single value.
+    ///
+    /// As per [RFC 9110, section 5.3](https://www.rfc-editor.org/rfc/rfc9110#section-5.3),
+    /// the values of repeated list-based headers are joined with commas.
+    pub fn get_list(&self, k: &str) -> Option<HeaderValue> {
+        combine(self.get_all(k)?)
+    }
+}
+
+/// Split raw HTTP/1.x bytes into numbered lines without line endings
+pub(crate) fn raw_lines(raw: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    raw.split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+}
+
+/// Combine the values of a list-based header
+pub(crate) fn combine(values: &[HeaderValue]) -> Option<HeaderValue> {
+    match values {
+        [] => None,
+        [value] => Some(value.clone()),
+        values => {
+            let combined = values
+                .iter()
+                .map(HeaderValue::as_bytes)
+                .collect::<Vec<_>>()
+                .join(&b", "[..]);
+            HeaderValue::from_bytes(&combined).ok()
+        }
     }
 }
 
+/// Get the value of a header which must only occur once.
+///
+/// Repeated identical values are accepted. If the values differ,
+/// the first one is used and a warning is added.
+pub(crate) fn singleton<'a>(
+    name: &str,
+    values: &'a [HeaderValue],
+    warnings: &mut Vec<Warning>,
+) -> Option<&'a HeaderValue> {
+    let (first, rest) = values.split_first()?;
+    if rest.iter().any(|value| value != first) {
+        warnings.push(Warning::ConflictingValues(
+            name.to_string(),
+            values
+                .iter()
+                .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+                .collect(),
+        ));
+    }
+    Some(first)
+}
+
 impl FromStr for CaseSensitiveHeaderMap {
     type Err = Error;
 
+    /// Parse headers separated by newlines.
+    ///
+    /// Empty lines, indentation and a leading HTTP status line are ignored,
+    /// as are stray lines without a colon. Use
+    /// [`CaseSensitiveHeaderMap::from_http1`] to parse a raw response
+    /// with folded header lines and a body.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidHeaderLine`] with the line number of the first
+    /// line with an invalid header name or value.
     fn from_str(headers: &str) -> Result<Self> {
-        Ok(CaseSensitiveHeaderMap {
-            inner: headers
-                .lines()
-                .filter_map(|line| line.split_once(HEADER_SEPARATOR))
-                .map(|(header, value)| {
-                    (
-                        header.to_string(),
-                        HeaderValue::from_str(value.trim()).unwrap(),
-                    )
-                })
-                .collect(),
-        })
+        let mut map = Self::new();
+        let mut first = true;
+        for (index, line) in headers.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || std::mem::take(&mut first) && line.starts_with("HTTP/") {
+                continue;
+            }
+            match map.insert_line(line.as_bytes()) {
+                Err(Error::HeaderWithoutColon(_)) => {}
+                result => result.map_err(|e| Error::InvalidHeaderLine(index + 1, Box::new(e)))?,
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl TryFrom<&str> for CaseSensitiveHeaderMap {
+    type Error = Error;
+
+    fn try_from(headers: &str) -> Result<Self> {
+        CaseSensitiveHeaderMap::from_str(headers)
+    }
+}
+
+impl TryFrom<&String> for CaseSensitiveHeaderMap {
+    type Error = Error;
+
+    fn try_from(headers: &String) -> Result<Self> {
+        CaseSensitiveHeaderMap::from_str(headers)
+    }
+}
+
+/// Fallible conversion into a [`CaseSensitiveHeaderMap`]
+///
+/// This is implemented for all types which implement
+/// `TryInto<CaseSensitiveHeaderMap>` with an error convertible into
+/// [`Error`], e.g. `&str`, [`http::HeaderMap`] and the map itself.
+/// It is accepted by all parsers of this crate.
+pub trait TryIntoHeaderMap {
+    /// Convert into a header map
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the headers cannot be parsed.
+    fn try_into_header_map(self) -> Result<CaseSensitiveHeaderMap>;
+}
+
+impl<T> TryIntoHeaderMap for T
+where
+    T: TryInto<CaseSensitiveHeaderMap>,
+    Error: From<T::Error>,
+{
+    fn try_into_header_map(self) -> Result<CaseSensitiveHeaderMap> {
+        Ok(self.try_into()?)
     }
 }
 
-impl From<&str> for CaseSensitiveHeaderMap {
-    fn from(headers: &str) -> Self {
-        CaseSensitiveHeaderMap::from_str(headers).unwrap()
+impl<K: Into<String>> FromIterator<(K, HeaderValue)> for CaseSensitiveHeaderMap {
+    /// Collect headers from name/value pairs, keeping the casing of the names.
+    ///
+    /// Use this for HTTP clients which expose the original header names.
+    fn from_iter<I: IntoIterator<Item = (K, HeaderValue)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (name, value) in iter {
+            map.append(name.into(), value);
+        }
+        map
     }
 }
 
 impl From<HeaderMap> for CaseSensitiveHeaderMap {
     fn from(headers: HeaderMap) -> Self {
-        let mut cs_map = CaseSensitiveHeaderMap::new();
-        for (name, value) in headers.iter() {
-            cs_map.insert(name.as_str().to_string(), value.clone());
-        }
-        cs_map
+        CaseSensitiveHeaderMap::from(&headers)
     }
 }
 
@@ -82,18 +363,22 @@ impl From<&HeaderMap> for CaseSensitiveHeaderMap {
     fn from(headers: &HeaderMap) -> Self {
         let mut cs_map = CaseSensitiveHeaderMap::new();
         for (name, value) in headers.iter() {
-            cs_map.insert(name.as_str().to_string(), value.clone());
+            cs_map.append(name.as_str().to_string(), value.clone());
         }
+        // `http` lowercases all header names
+        cs_map.preserves_case = false;
         cs_map
     }
 }
 
 /// Extension trait for `HeaderMap` to convert from raw string.
+#[cfg(test)]
 pub(crate) trait HeaderMapExt {
     /// Convert from raw string.
     fn from_raw(raw: &str) -> Result<HeaderMap>;
 }
 
+#[cfg(test)]
 impl HeaderMapExt for HeaderMap {
     fn from_raw(raw: &str) -> Result<HeaderMap> {
         let mut headers = HeaderMap::new();
@@ -104,7 +389,7 @@ impl HeaderMapExt for HeaderMap {
             }
             if let Some((name, value)) = line.split_once(HEADER_SEPARATOR) {
                 headers.insert(
-                    HeaderName::from_str(name)?,
+                    headers::HeaderName::from_str(name)?,
                     HeaderValue::from_str(value.trim())?,
                 );
             }
@@ -131,20 +416,195 @@ mod tests {
                 inner: vec![
                     (
                         "x-ratelimit-limit".to_string(),
-                        HeaderValue::from_static("100")
+                        vec![HeaderValue::from_static("100")]
                     ),
                     (
                         "x-ratelimit-remaining".to_string(),
-                        HeaderValue::from_static("99")
+                        vec![HeaderValue::from_static("99")]
                     ),
                     (
                         "x-ratelimit-reset".to_string(),
-                        HeaderValue::from_static("1234567890")
+                        vec![HeaderValue::from_static("1234567890")]
                     )
                 ]
                 .into_iter()
-                .collect()
+                .collect(),
+                preserves_case: false,
             }
         );
     }
+
+    #[test]
+    fn keep_repeated_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append("RateLimit", "\"a\";r=1;t=2".parse().unwrap());
+        headers.append("RateLimit", "\"b\";r=3;t=4".parse().unwrap());
+
+        let cs_headers = CaseSensitiveHeaderMap::from(&headers);
+        assert_eq!(cs_headers.get_all("ratelimit").unwrap().len(), 2);
+        assert_eq!(
+            cs_headers.get_list("ratelimit").unwrap(),
+            "\"a\";r=1;t=2, \"b\";r=3;t=4"
+        );
+
+        let headers =
+            CaseSensitiveHeaderMap::from_str("Retry-After: 10\nRetry-After: 10\nRetry-After: 20")
+                .unwrap();
+        let values = headers.get_all("Retry-After").unwrap();
+        assert_eq!(values, ["10", "10", "20"]);
+
+        let mut warnings = Vec::new();
+        assert_eq!(
+            singleton("Retry-After", &values[..2], &mut warnings).unwrap(),
+            "10"
+        );
+        assert!(warnings.is_empty());
+        assert_eq!(
+            singleton("Retry-After", values, &mut warnings).unwrap(),
+            "10"
+        );
+        assert_eq!(
+            warnings,
+            vec![Warning::ConflictingValues(
+                "Retry-After".to_string(),
+                vec!["10".to_string(), "10".to_string(), "20".to_string()]
+            )]
+        );
+    }
+
+    #[test]
+    fn parse_raw_http1_response() {
+        let raw = b"HTTP/1.1 429 Too Many Requests\r\n\
+            X-RateLimit-Limit: 60\r\n\
+            X-RateLimit-Remaining:0\r\n\
+            \r\n\
+            Not-A-Header: body";
+        let headers = CaseSensitiveHeaderMap::from_http1(raw).unwrap();
+        assert!(headers.preserves_case());
+        assert_eq!(
+            headers.get("X-RateLimit-Limit"),
+            Some(&HeaderValue::from_static("60"))
+        );
+        assert_eq!(
+            headers.get("X-RateLimit-Remaining"),
+            Some(&HeaderValue::from_static("0"))
+        );
+        assert_eq!(headers.get("x-ratelimit-limit"), None);
+        assert_eq!(headers.get("Not-A-Header"), None);
+
+        // Folded lines continue the previous header
+        let raw = b"HTTP/1.1 200 OK\r\nRateLimit: \"a\";r=1;t=2,\r\n \"b\";r=3;t=4\r\n\r\n";
+        let headers = CaseSensitiveHeaderMap::from_http1(raw).unwrap();
+        assert_eq!(
+            headers.get("RateLimit").unwrap(),
+            "\"a\";r=1;t=2, \"b\";r=3;t=4"
+        );
+
+        // The status line is optional
+        let headers = CaseSensitiveHeaderMap::from_http1(b"RateLimit-Limit: 1\n").unwrap();
+        assert!(headers.get("RateLimit-Limit").is_some());
+
+        assert!(matches!(
+            CaseSensitiveHeaderMap::from_http1(b"HTTP/1.1 200 OK\r\nno colon\r\n"),
+            Err(Error::InvalidHeaderLine(2, e)) if matches!(*e, Error::HeaderWithoutColon(_))
+        ));
+        assert!(matches!(
+            CaseSensitiveHeaderMap::from_http1(b"HTTP/1.1 200 OK\r\nA: 1\r\nBad Name: 1\r\n"),
+            Err(Error::InvalidHeaderLine(3, e)) if matches!(*e, Error::InvalidHeaderName(_))
+        ));
+    }
+
+    #[test]
+    fn parse_invalid_header_lines() {
+        let headers =
+            CaseSensitiveHeaderMap::from_str("\nHTTP/1.1 200 OK\nRetry-After: 30\n").unwrap();
+        assert!(headers.get("Retry-After").is_some());
+
+        // A body without colons is ignored
+        let raw = "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 30\r\n\r\nSlow down!";
+        let headers = CaseSensitiveHeaderMap::from_str(raw).unwrap();
+        assert!(headers.get("Slow down!").is_none());
+        let rate = crate::RateLimit::from_str(raw).unwrap();
+        assert_eq!(rate.reset(), Some(crate::ResetTime::Seconds(30)));
+
+        // Indented lines and lines after an empty line are headers as well
+        let headers =
+            CaseSensitiveHeaderMap::from_str("  RateLimit-Limit: 60\n\n  RateLimit-Remaining: 59")
+                .unwrap();
+        assert_eq!(headers.get("RateLimit-Limit").unwrap(), "60");
+        assert_eq!(headers.get("RateLimit-Remaining").unwrap(), "59");
+
+        assert!(matches!(
+            CaseSensitiveHeaderMap::from_str("Retry-After: 30\nX: \u{7f}bad"),
+            Err(Error::InvalidHeaderLine(2, e)) if matches!(*e, Error::InvalidHeaderValue(_))
+        ));
+        assert!(matches!(
+            CaseSensitiveHeaderMap::try_from("Bad Name: 30"),
+            Err(Error::InvalidHeaderLine(1, e)) if matches!(*e, Error::InvalidHeaderName(_))
+        ));
+
+        // Stray lines without a colon are skipped
+        let headers = CaseSensitiveHeaderMap::from_str("Retry-After: 30\nfoo").unwrap();
+        assert_eq!(headers.get("Retry-After").unwrap(), "30");
+        assert!(crate::RateLimit::from_str("Retry-After: 30\nfoo").is_ok());
+    }
+
+    #[test]
+    fn merge_header_casings() {
+        let headers = CaseSensitiveHeaderMap::from_str(indoc::indoc! {"
+            x-ratelimit-remaining: 3
+            X-RateLimit-Remaining: 1
+            X-RATELIMIT-REMAINING: 2
+            X-RateLimit-Limit: 60
+            x-ratelimit-limit: 60
+        "})
+        .unwrap();
+
+        // The exact match comes first, then the other casings in order
+        assert_eq!(
+            headers
+                .get_all_ignore_case("X-RateLimit-Remaining")
+                .unwrap()
+                .as_ref(),
+            ["1", "2", "3"]
+        );
+        assert_eq!(
+            headers
+                .get_all_ignore_case("x-RateLimit-remaining")
+                .unwrap()
+                .as_ref(),
+            ["2", "1", "3"]
+        );
+        assert_eq!(
+            headers.get_ignore_case("x-ratelimit-remaining").unwrap(),
+            "3"
+        );
+        assert!(headers.get_all_ignore_case("Retry-After").is_none());
+
+        assert_eq!(
+            headers.conflicting_casings("X-RateLimit-Remaining"),
+            Some(Warning::ConflictingCasings(
+                "X-RateLimit-Remaining".to_string(),
+                vec![
+                    "X-RateLimit-Remaining".to_string(),
+                    "X-RATELIMIT-REMAINING".to_string(),
+                    "x-ratelimit-remaining".to_string(),
+                ]
+            ))
+        );
+        assert_eq!(headers.conflicting_casings("X-RateLimit-Limit"), None);
+    }
+
+    #[test]
+    fn collect_header_pairs() {
+        let headers: CaseSensitiveHeaderMap = [
+            ("RateLimit-Limit", HeaderValue::from_static("60")),
+            ("RateLimit-Remaining", HeaderValue::from_static("59")),
+        ]
+        .into_iter()
+        .collect();
+        assert!(headers.preserves_case());
+        assert!(headers.get("RateLimit-Limit").is_some());
+        assert!(headers.get("ratelimit-limit").is_none());
+    }
 }
@@ -0,0 +1,87 @@
+//! Sources of the current time
+//!
+//! Reset times like [`ResetTime::DateTime`](crate::ResetTime::DateTime) are
+//! only meaningful relative to the current time. All computations which
+//! depend on it take a [`Clock`], so they can be tested deterministically or
+//! replayed against recorded traffic.
+use std::sync::{Mutex, PoisonError};
+
+use time::{Duration, OffsetDateTime};
+
+/// A source of the current time
+pub trait Clock {
+    /// Get the current time
+    fn now(&self) -> OffsetDateTime;
+}
+
+/// The system clock
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// A clock which only changes when it is set or advanced explicitly
+///
+/// ```
+/// use rate_limits::clock::{Clock, ManualClock};
+/// use time::{macros::datetime, Duration};
+///
+/// let clock = ManualClock::new(datetime!(2023-03-01 10:00:00 UTC));
+/// clock.advance(Duration::seconds(30));
+/// assert_eq!(clock.now(), datetime!(2023-03-01 10:00:30 UTC));
+/// ```
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<OffsetDateTime>,
+}
+
+impl ManualClock {
+    /// Create a new clock which is set to the given time
+    #[must_use]
+    pub const fn new(now: OffsetDateTime) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    /// Set the clock to the given time
+    pub fn set(&self, now: OffsetDateTime) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) = now;
+    }
+
+    /// Move the clock forward by the given duration
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> OffsetDateTime {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The clock of a server, which is offset from a local clock by a skew
+///
+/// A positive skew means that the server clock is ahead.
+pub(crate) struct ServerClock<'a, C: Clock + ?Sized> {
+    clock: &'a C,
+    skew: Duration,
+}
+
+impl<'a, C: Clock + ?Sized> ServerClock<'a, C> {
+    pub(crate) const fn new(clock: &'a C, skew: Duration) -> Self {
+        Self { clock, skew }
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for ServerClock<'_, C> {
+    fn now(&self) -> OffsetDateTime {
+        let now = self.clock.now();
+        now.checked_add(self.skew).unwrap_or(now)
+    }
+}
@@ -0,0 +1,217 @@
+//! Parsing of duration strings
+//!
+//! Some APIs send the time until a rate limit is lifted as a duration string
+//! instead of a number of seconds, e.g. `6m0s` or `PT30S`.
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+const NANOS_PER_MINUTE: u128 = 60 * NANOS_PER_SECOND;
+const NANOS_PER_HOUR: u128 = 60 * NANOS_PER_MINUTE;
+const NANOS_PER_DAY: u128 = 24 * NANOS_PER_HOUR;
+
+/// Parse a non-negative decimal number of seconds like `1.234`.
+///
+/// The fraction is parsed exactly up to nanoseconds; further digits are
+/// truncated.
+pub(crate) fn parse_seconds(value: &str) -> Result<Duration> {
+    let invalid = || Error::InvalidDuration(value.to_string());
+    match split_number(value.trim(), &['.']) {
+        Some((whole, fraction, "")) => scale(whole, fraction, NANOS_PER_SECOND)
+            .and_then(from_nanos)
+            .ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse a duration as formatted by Go's `time.Duration`, e.g. `1h2m3.5s`.
+///
+/// See <https://pkg.go.dev/time#ParseDuration>.
+/// Negative durations are rejected.
+pub(crate) fn parse_go(value: &str) -> Result<Duration> {
+    let invalid = || Error::InvalidDuration(value.to_string());
+    let trimmed = value.trim();
+    let mut rest = trimmed.strip_prefix('+').unwrap_or(trimmed);
+    if rest == "0" {
+        return Ok(Duration::ZERO);
+    }
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut nanos: u128 = 0;
+    while !rest.is_empty() {
+        let (whole, fraction, tail) = split_number(rest, &['.']).ok_or_else(invalid)?;
+        let (unit, tail) = tail.split_at(
+            tail.find(|c: char| c.is_ascii_digit() || c == '.')
+                .unwrap_or(tail.len()),
+        );
+        let unit = match unit {
+            "ns" => 1,
+            "us" | "µs" | "μs" => 1_000,
+            "ms" => 1_000_000,
+            "s" => NANOS_PER_SECOND,
+            "m" => NANOS_PER_MINUTE,
+            "h" => NANOS_PER_HOUR,
+            _ => return Err(invalid()),
+        };
+        nanos = scale(whole, fraction, unit)
+            .and_then(|component| nanos.checked_add(component))
+            .ok_or_else(invalid)?;
+        rest = tail;
+    }
+    from_nanos(nanos).ok_or_else(invalid)
+}
+
+/// Parse an ISO 8601 duration, e.g. `PT30S` or `P1DT12H`.
+///
+/// Years and months are rejected, because their length depends on the date
+/// they are counted from.
+pub(crate) fn parse_iso8601(value: &str) -> Result<Duration> {
+    let invalid = || Error::InvalidDuration(value.to_string());
+    let rest = value.trim().strip_prefix('P').ok_or_else(invalid)?;
+    let (date, time) = match rest.split_once('T') {
+        Some((_, "")) => return Err(invalid()),
+        Some((date, time)) => (date, time),
+        None if rest.is_empty() => return Err(invalid()),
+        None => (rest, ""),
+    };
+    let mut nanos: u128 = 0;
+    components(
+        date,
+        &[('W', 7 * NANOS_PER_DAY), ('D', NANOS_PER_DAY)],
+        &mut nanos,
+    )
+    .and_then(|()| {
+        components(
+            time,
+            &[
+                ('H', NANOS_PER_HOUR),
+                ('M', NANOS_PER_MINUTE),
+                ('S', NANOS_PER_SECOND),
+            ],
+            &mut nanos,
+        )
+    })
+    .and_then(|()| from_nanos(nanos))
+    .ok_or_else(invalid)
+}
+
+/// Add up the components of one part of an ISO 8601 duration.
+///
+/// Each component is a number followed by one of the given designators,
+/// which have to appear in order.
+fn components(mut part: &str, units: &[(char, u128)], nanos: &mut u128) -> Option<()> {
+    let mut units = units.iter();
+    while !part.is_empty() {
+        let (whole, fraction, tail) = split_number(part, &['.', ','])?;
+        let designator = tail.chars().next()?;
+        let (_, unit) = units.find(|(d, _)| *d == designator)?;
+        *nanos = nanos.checked_add(scale(whole, fraction, *unit)?)?;
+        part = &tail[designator.len_utf8()..];
+    }
+    Some(())
+}
+
+/// Split a leading decimal number like `1.5` into its whole part,
+/// its fraction and the rest of the value.
+fn split_number<'a>(value: &'a str, separators: &[char]) -> Option<(&'a str, &'a str, &'a str)> {
+    let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (whole, rest) = value.split_at(digits(value));
+    let (fraction, rest) = match rest.strip_prefix(separators) {
+        Some(rest) => rest.split_at(digits(rest)),
+        None => ("", rest),
+    };
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    Some((whole, fraction, rest))
+}
+
+/// Convert a decimal number of `unit`s to nanoseconds.
+///
+/// Digits of the fraction beyond nanosecond precision are ignored.
+fn scale(whole: &str, fraction: &str, unit: u128) -> Option<u128> {
+    let whole = if whole.is_empty() {
+        0
+    } else {
+        whole.parse::<u128>().ok()?
+    };
+    let fraction = &fraction[..fraction.len().min(18)];
+    let fraction = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u128>().ok()? * unit / 10u128.pow(fraction.len() as u32)
+    };
+    whole.checked_mul(unit)?.checked_add(fraction)
+}
+
+fn from_nanos(nanos: u128) -> Option<Duration> {
+    let seconds = u64::try_from(nanos / NANOS_PER_SECOND).ok()?;
+    // The remainder is always less than one billion
+    Some(Duration::new(seconds, (nanos % NANOS_PER_SECOND) as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fractional_seconds() {
+        assert_eq!(parse_seconds("1.234").unwrap(), Duration::from_millis(1234));
+        assert_eq!(parse_seconds(" 30 ").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_seconds(".5").unwrap(), Duration::from_millis(500));
+        assert_eq!(
+            parse_seconds("1470173023.1234567891").unwrap(),
+            Duration::new(1_470_173_023, 123_456_789)
+        );
+        for value in ["", ".", "-1.5", "+1.5", "1.5.1", "1,5", "1e3"] {
+            assert!(
+                matches!(parse_seconds(value), Err(Error::InvalidDuration(_))),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_go_durations() {
+        for (value, expected) in [
+            ("6m0s", Duration::from_secs(360)),
+            ("17ms", Duration::from_millis(17)),
+            ("1h2m", Duration::from_secs(3720)),
+            ("1.5s", Duration::from_millis(1500)),
+            ("2h45m30.25s", Duration::from_millis(9_930_250)),
+            ("300us", Duration::from_micros(300)),
+            ("1µs", Duration::from_micros(1)),
+            ("42ns", Duration::from_nanos(42)),
+            (".5m", Duration::from_secs(30)),
+            ("0", Duration::ZERO),
+            ("+5s", Duration::from_secs(5)),
+        ] {
+            assert_eq!(parse_go(value).unwrap(), expected, "{value}");
+        }
+        for value in ["", "5", "-5s", "5x", "1h 2m", "s", ".s", "1..5s"] {
+            assert!(parse_go(value).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn parse_iso8601_durations() {
+        for (value, expected) in [
+            ("PT30S", Duration::from_secs(30)),
+            ("PT1.5S", Duration::from_millis(1500)),
+            ("PT0,25S", Duration::from_millis(250)),
+            ("PT1H2M", Duration::from_secs(3720)),
+            ("P1DT12H", Duration::from_secs(36 * 3600)),
+            ("P2W", Duration::from_secs(14 * 24 * 3600)),
+            ("PT0S", Duration::ZERO),
+        ] {
+            assert_eq!(parse_iso8601(value).unwrap(), expected, "{value}");
+        }
+        for value in [
+            "", "P", "PT", "30S", "PT30", "P1Y", "P1M", "PT1S1M", "PT-1S", "pt30s",
+        ] {
+            assert!(parse_iso8601(value).is_err(), "{value}");
+        }
+    }
+}
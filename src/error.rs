@@ -18,15 +18,36 @@ pub enum Error {
     /// HTTP x-ratelimit-reset header not found
     MissingReset,
 
+    /// Invalid rate limit header value: {0}
+    InvalidLimit(String),
+
     /// HTTP Retry-After header not found
     MissingRetryAfter,
 
     /// Invalid Retry-After header value
     InvalidRetryAfter(String),
 
+    /// HTTP RateLimit-Policy header not found
+    MissingPolicy,
+
+    /// Invalid RateLimit-Policy header value: {0}
+    InvalidPolicy(String),
+
+    /// HTTP RateLimit header not found
+    MissingRateLimit,
+
+    /// Invalid RateLimit header value: {0}
+    InvalidRateLimit(String),
+
     /// Header does not contain colon
     HeaderWithoutColon(String),
 
+    /// Invalid HTTP status line: {0}
+    InvalidStatusLine(String),
+
+    /// Invalid header in line {0}: {1}
+    InvalidHeaderLine(usize, #[source] Box<Error>),
+
     /// Invalid header name
     InvalidHeaderName(#[from] http::header::InvalidHeaderName),
 
@@ -39,9 +60,45 @@ pub enum Error {
     /// Cannot parse rate limit header value: {0}
     InvalidValue(#[from] ParseIntError),
 
+    /// Unexpected end of structured field value
+    UnexpectedEnd,
+
+    /// Unexpected character {0:?} at position {1} of structured field value
+    UnexpectedCharacter(char, usize),
+
+    /// Invalid structured field integer: {0}
+    InvalidInteger(String),
+
+    /// Invalid structured field decimal: {0}
+    InvalidDecimal(String),
+
+    /// Invalid structured field byte sequence: {0}
+    InvalidByteSequence(String),
+
+    /// Incomplete rate limit variant: missing {0} header name
+    IncompleteVariant(&'static str),
+
+    /// Unknown reset time kind: {0}
+    UnknownResetKind(String),
+
+    /// Reset time {0:?} does not match the format of any rate limit variant
+    MismatchedReset(String),
+
+    /// Cannot detect the kind of reset time: {0}
+    UndetectableResetKind(String),
+
+    /// Invalid rate limit variant configuration: {0}
+    InvalidConfig(String),
+
     /// Cannot lock header map
     Lock,
 
+    /// Invalid duration: {0}
+    InvalidDuration(String),
+
+    /// Invalid HTTP date: {0}
+    InvalidHttpDate(String),
+
     /// Time Parsing error
     Parse(#[from] time::error::Parse),
 
@@ -49,4 +106,19 @@ pub enum Error {
     Time(#[from] time::error::ComponentRange),
 }
 
+/// Non-fatal problems found while parsing the rate limit headers
+#[derive(Display, Clone, Debug, PartialEq, Eq)]
+pub enum Warning {
+    /// Conflicting values {1:?} for header {0}, using the first one
+    ConflictingValues(String, Vec<String>),
+    /// Header {0} was sent with several casings {1:?} and different values
+    ConflictingCasings(String, Vec<String>),
+}
+
+impl From<std::convert::Infallible> for Error {
+    fn from(infallible: std::convert::Infallible) -> Self {
+        match infallible {}
+    }
+}
+
 pub(crate) type Result<T> = std::result::Result<T, Error>;
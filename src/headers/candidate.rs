@@ -0,0 +1,310 @@
+use std::borrow::Cow;
+
+use headers::HeaderValue;
+use time::OffsetDateTime;
+
+use super::registry::VariantRegistry;
+use super::types::RateLimitVariant;
+use crate::casesensitive_headermap::CaseSensitiveHeaderMap;
+use crate::error::{Error, Result, Warning};
+use crate::reset_time::ResetTimeKind;
+
+/// How header names of a variant are matched against a header map
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    /// Header names must match exactly, including their casing.
+    ///
+    /// This tells apart vendors which only differ by the casing of their
+    /// header names, but fails for HTTP stacks which lowercase all headers.
+    #[default]
+    CaseSensitive,
+    /// Header names match regardless of their casing.
+    ///
+    /// Variants with exactly matching names are preferred
+    /// if the casing of the header map is known.
+    IgnoreCase,
+}
+
+/// A variant whose headers were found in a header map
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariantMatch {
+    /// The matching variant
+    pub variant: RateLimitVariant,
+    /// Number of header names of the variant which are present
+    /// (limit, used, remaining and reset)
+    pub present: usize,
+    /// Confidence in the match between 0 and 1,
+    /// see [`Headers::confidence`](super::Headers::confidence)
+    pub confidence: f32,
+}
+
+/// A variant whose headers are all present in a header map
+#[derive(Clone, Debug)]
+pub(crate) struct Candidate<'a> {
+    pub(crate) variant: &'a RateLimitVariant,
+    pub(crate) limit: Option<Cow<'a, [HeaderValue]>>,
+    pub(crate) used: Option<Cow<'a, [HeaderValue]>>,
+    pub(crate) remaining: Cow<'a, [HeaderValue]>,
+    /// Values of the reset header, if the variant has one
+    pub(crate) reset: Option<Cow<'a, [HeaderValue]>>,
+    pub(crate) present: usize,
+    pub(crate) confidence: f32,
+    /// Whether the reset value is clearly of another kind than the one
+    /// of the variant, e.g. seconds for a variant with timestamps
+    pub(crate) contradicted: bool,
+    /// Headers which were sent with several casings and different values
+    pub(crate) warnings: Vec<Warning>,
+}
+
+impl<'a> Candidate<'a> {
+    fn new(
+        variant: &'a RateLimitVariant,
+        headers: &'a CaseSensitiveHeaderMap,
+        mode: MatchMode,
+        now: OffsetDateTime,
+    ) -> Option<Self> {
+        let get = |name: Option<&str>| {
+            name.and_then(|name| match mode {
+                MatchMode::CaseSensitive => headers.get_all(name).map(Cow::Borrowed),
+                MatchMode::IgnoreCase => headers.get_all_ignore_case(name),
+            })
+        };
+        let limit = get(variant.limit_header.as_deref());
+        let used = get(variant.used_header.as_deref());
+        if limit.is_none() && used.is_none() {
+            return None;
+        }
+        let remaining = get(Some(&variant.remaining_header))?;
+        let reset = match &variant.reset_header {
+            Some(name) => Some(get(Some(name))?),
+            None => None,
+        };
+        // The kind is only detected to rank the variant. The value is
+        // parsed with the kind of the variant, unless it is `Auto`.
+        let detected = reset
+            .as_deref()
+            .and_then(<[_]>::first)
+            .and_then(|reset| reset.to_str().ok())
+            .and_then(|reset| ResetTimeKind::detect_at(reset, now));
+
+        let names = [
+            variant.limit_header.as_deref(),
+            variant.used_header.as_deref(),
+            Some(variant.remaining_header.as_str()),
+            variant.reset_header.as_deref(),
+        ];
+        let defined = names.iter().flatten().count();
+        let present = names.iter().filter(|name| get(**name).is_some()).count();
+        let exact = names
+            .iter()
+            .flatten()
+            .filter(|name| headers.get(name).is_some())
+            .count();
+
+        // The format of the reset value is a stronger hint than the casing
+        // of the header names, which gets lost in many HTTP libraries
+        // Variants which detect the kind fit every detectable value
+        // Variants without a reset header have no value to contradict them
+        let fits = |kind| kind == variant.reset_kind || variant.reset_kind == ResetTimeKind::Auto;
+        let contradicted = detected.is_some_and(|kind| !fits(kind));
+        let value = if reset.is_none() || detected.is_some_and(fits) {
+            1.0
+        } else {
+            0.0
+        };
+        // Without the original casing, only the values are significant
+        let confidence = if headers.preserves_case() {
+            let names = exact as f32 / defined as f32;
+            (3.0 * value + 2.0 * names) / 5.0
+        } else {
+            value
+        };
+        let warnings = match mode {
+            MatchMode::CaseSensitive => Vec::new(),
+            MatchMode::IgnoreCase => names
+                .iter()
+                .flatten()
+                .filter_map(|name| headers.conflicting_casings(name))
+                .collect(),
+        };
+
+        Some(Self {
+            variant,
+            limit,
+            used,
+            remaining,
+            reset,
+            present,
+            confidence,
+            contradicted,
+            warnings,
+        })
+    }
+
+    /// Error for a reset value which contradicts the kind of the variant
+    pub(crate) fn mismatched_reset(&self) -> Error {
+        let value = self
+            .reset
+            .as_deref()
+            .and_then(<[_]>::first)
+            .and_then(|reset| reset.to_str().ok())
+            .unwrap_or_default();
+        Error::MismatchedReset(value.to_string())
+    }
+}
+
+impl From<Candidate<'_>> for VariantMatch {
+    fn from(candidate: Candidate<'_>) -> Self {
+        Self {
+            variant: candidate.variant.clone(),
+            present: candidate.present,
+            confidence: candidate.confidence,
+        }
+    }
+}
+
+/// Get all variants of the registry whose headers are present.
+///
+/// Variants known to be sent by the host of the request rank first,
+/// because the host is a stronger hint than the headers. Otherwise, the
+/// variants are ranked by the number of present headers and then by
+/// confidence. Variants with the same rank keep the order of the registry.
+///
+/// The format of reset values is detected relative to `now`.
+///
+/// # Errors
+///
+/// Returns an error for the first kind of header which is missing for all
+/// variants if there is no candidate.
+pub(crate) fn candidates<'a>(
+    headers: &'a CaseSensitiveHeaderMap,
+    registry: &'a VariantRegistry,
+    now: OffsetDateTime,
+) -> Result<Vec<Candidate<'a>>> {
+    let mode = registry.match_mode();
+    let mut candidates: Vec<_> = registry
+        .iter()
+        .filter_map(|variant| Candidate::new(variant, headers, mode, now))
+        .collect();
+    if candidates.is_empty() {
+        return Err(missing(headers, registry));
+    }
+    candidates.sort_by(|a, b| {
+        let host = |c: &Candidate<'_>| registry.matches_host(c.variant);
+        host(b)
+            .cmp(&host(a))
+            .then(b.present.cmp(&a.present))
+            .then(b.confidence.total_cmp(&a.confidence))
+    });
+    Ok(candidates)
+}
+
+/// Find out which header is missing for all variants
+fn missing(headers: &CaseSensitiveHeaderMap, registry: &VariantRegistry) -> Error {
+    let present = |name: Option<&str>| {
+        name.is_some_and(|name| match registry.match_mode() {
+            MatchMode::CaseSensitive => headers.get(name).is_some(),
+            MatchMode::IgnoreCase => headers.get_ignore_case(name).is_some(),
+        })
+    };
+
+    if !registry
+        .iter()
+        .any(|v| present(Some(v.remaining_header.as_str())))
+    {
+        Error::MissingRemaining
+    } else if !registry
+        .iter()
+        .any(|v| present(v.limit_header.as_deref()) || present(v.used_header.as_deref()))
+    {
+        Error::MissingUsed
+    } else {
+        Error::MissingReset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::Vendor;
+    use crate::ResetTime;
+    use indoc::indoc;
+
+    fn ignore_case() -> VariantRegistry {
+        let mut registry = VariantRegistry::default();
+        registry.set_match_mode(MatchMode::IgnoreCase);
+        registry
+    }
+
+    #[test]
+    fn rank_candidates() {
+        let headers = CaseSensitiveHeaderMap::try_from(indoc! {"
+            x-ratelimit-limit: 60
+            x-ratelimit-remaining: 59
+            x-ratelimit-reset: Sat, 01 Jan 2022 00:00:00 GMT
+            x-ratelimit-next: 2022-01-01T00:00:00Z
+        "})
+        .unwrap();
+        let matches = VariantRegistry::default()
+            .candidates(headers.clone())
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].variant.vendor, Vendor::Github);
+
+        let matches = ignore_case().candidates(headers).unwrap();
+        let vendors: Vec<_> = matches.iter().map(|m| m.variant.vendor.clone()).collect();
+        assert_eq!(vendors, vec![Vendor::Vimeo, Vendor::Akamai, Vendor::Github]);
+        assert_eq!(matches[0].present, 3);
+        assert_eq!(matches[0].confidence, 0.6);
+        assert_eq!(matches[2].confidence, 0.4);
+    }
+
+    #[test]
+    fn rank_by_host_first() {
+        // Gitlab sends timestamps, so it fits the value worse than the
+        // standard headers, but the host matches.
+        let headers = indoc! {"
+            RateLimit-Limit: 60
+            RateLimit-Remaining: 5
+            RateLimit-Reset: 30
+        "};
+        let registry = ignore_case();
+        let matches = registry.candidates(headers).unwrap();
+        assert_eq!(matches[0].variant.vendor, Vendor::Standard);
+
+        let registry = registry.for_host("https://gitlab.com/api/v4");
+        let matches = registry.candidates(headers).unwrap();
+        assert_eq!(matches[0].variant.vendor, Vendor::Gitlab);
+        assert!(matches[0].confidence < matches[1].confidence);
+
+        // The value contradicts the timestamps of Gitlab, so it is not parsed
+        // as an instant in 1970
+        let rate = crate::Headers::with_registry(headers, &registry).unwrap();
+        assert_eq!(rate.vendor, Vendor::Standard);
+        assert_eq!(rate.reset, Some(ResetTime::Seconds(30)));
+    }
+
+    #[test]
+    fn rank_by_present_headers() {
+        let headers = CaseSensitiveHeaderMap::try_from(indoc! {"
+            ratelimit-limit: 60
+            ratelimit-observed: 1
+            ratelimit-remaining: 59
+            ratelimit-reset: 30
+        "})
+        .unwrap();
+        let registry = ignore_case();
+        let matches = registry.candidates(headers.clone()).unwrap();
+        assert_eq!(matches[0].variant.vendor, Vendor::Gitlab);
+        assert_eq!(matches[0].present, 4);
+        assert_eq!(matches[0].confidence, 0.0);
+        assert_eq!(matches[1].variant.vendor, Vendor::Standard);
+        assert_eq!(matches[1].present, 3);
+
+        // Gitlab has the most headers, but its reset value would be a
+        // timestamp in 1970
+        let rate = crate::Headers::with_registry(headers, &registry).unwrap();
+        assert_eq!(rate.vendor, Vendor::Standard);
+        assert_eq!(rate.reset, Some(ResetTime::Seconds(30)));
+    }
+}
@@ -0,0 +1,246 @@
+//! Rate limit variants loaded from TOML or JSON configuration
+//!
+//! Requires the `config` feature.
+
+use serde::Deserialize;
+use time::Duration;
+
+use super::registry::VariantRegistry;
+use super::types::{RateLimitVariant, Vendor};
+use crate::error::{Error, Result};
+
+/// Configuration of a registry, as read from a file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegistryConfig {
+    /// Keep the built-in variants after the configured ones.
+    /// Built-in variants of a configured vendor are replaced.
+    #[serde(default)]
+    defaults: bool,
+    #[serde(default, rename = "variant")]
+    variants: Vec<VariantConfig>,
+}
+
+/// Configuration of a single variant
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VariantConfig {
+    vendor: String,
+    /// Duration of the rate limit interval in seconds
+    window: Option<u32>,
+    limit_header: Option<String>,
+    used_header: Option<String>,
+    remaining_header: Option<String>,
+    reset_header: Option<String>,
+    reset_kind: Option<String>,
+    /// Hosts which are known to send these headers
+    #[serde(default)]
+    hosts: Vec<String>,
+}
+
+impl VariantConfig {
+    fn build(self) -> Result<RateLimitVariant> {
+        let mut builder = RateLimitVariant::builder(vendor(&self.vendor));
+        if let Some(window) = self.window {
+            builder = builder.duration(Duration::seconds(window.into()));
+        }
+        if let Some(name) = self.limit_header {
+            builder = builder.limit_header(name);
+        }
+        if let Some(name) = self.used_header {
+            builder = builder.used_header(name);
+        }
+        if let Some(name) = self.remaining_header {
+            builder = builder.remaining_header(name);
+        }
+        if let Some(name) = self.reset_header {
+            builder = builder.reset_header(name);
+        }
+        for host in self.hosts {
+            builder = builder.host(host);
+        }
+        if let Some(kind) = self.reset_kind {
+            builder = builder.reset_kind(kind.parse()?);
+        }
+        builder.build()
+    }
+}
+
+/// Map a vendor name to a built-in vendor, ignoring case
+fn vendor(name: &str) -> Vendor {
+    match name.to_ascii_lowercase().as_str() {
+        "standard" => Vendor::Standard,
+        "reddit" => Vendor::Reddit,
+        "github" => Vendor::Github,
+        "twitter" => Vendor::Twitter,
+        "vimeo" => Vendor::Vimeo,
+        "gitlab" => Vendor::Gitlab,
+        "akamai" => Vendor::Akamai,
+        "openai" => Vendor::OpenAi,
+        "hubspot" => Vendor::Hubspot,
+        _ => Vendor::Custom(name.to_string()),
+    }
+}
+
+impl RegistryConfig {
+    fn build(self) -> Result<VariantRegistry> {
+        let mut registry = self
+            .variants
+            .into_iter()
+            .map(VariantConfig::build)
+            .collect::<Result<VariantRegistry>>()?;
+
+        if self.defaults {
+            let defaults: Vec<_> = VariantRegistry::default()
+                .iter()
+                .filter(|default| registry.iter().all(|v| v.vendor != default.vendor))
+                .cloned()
+                .collect();
+            registry.extend(defaults);
+        }
+        Ok(registry)
+    }
+}
+
+impl VariantRegistry {
+    /// Load a registry from a TOML configuration.
+    ///
+    /// Each `[[variant]]` table defines a variant with a `vendor` name,
+    /// a `remaining_header`, a `limit_header` or `used_header` and a
+    /// `reset_header` or a `window` in seconds. Optional keys are the
+    /// `reset_kind` (`seconds`, `timestamp`, `imf-fixdate`, `iso8601`,
+    /// `fractional-seconds`, `fractional-timestamp`, `timestamp-millis`,
+    /// `go-duration`, `iso8601-duration` or `auto`)
+    /// and a list of `hosts` which send the headers.
+    /// Set `defaults = true` to keep the built-in variants after the
+    /// configured ones.
+    ///
+    /// ```
+    /// use rate_limits::headers::VariantRegistry;
+    ///
+    /// let registry = VariantRegistry::from_toml(r#"
+    ///     defaults = true
+    ///
+    ///     [[variant]]
+    ///     vendor = "Acme"
+    ///     window = 60
+    ///     used_header = "Acme-Used"
+    ///     remaining_header = "Acme-Remaining"
+    ///     reset_header = "Acme-Reset"
+    ///     reset_kind = "timestamp"
+    /// "#).unwrap();
+    /// assert_eq!(registry.iter().next().unwrap().remaining_header(), "Acme-Remaining");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the configuration cannot be parsed,
+    /// contains an unknown reset kind or an incomplete variant.
+    pub fn from_toml(config: &str) -> Result<Self> {
+        toml::from_str::<RegistryConfig>(config)
+            .map_err(|e| Error::InvalidConfig(e.to_string()))?
+            .build()
+    }
+
+    /// Load a registry from a JSON configuration.
+    ///
+    /// The format matches [`VariantRegistry::from_toml`], with variants
+    /// given as a `variant` array.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the configuration cannot be parsed,
+    /// contains an unknown reset kind or an incomplete variant.
+    pub fn from_json(config: &str) -> Result<Self> {
+        serde_json::from_str::<RegistryConfig>(config)
+            .map_err(|e| Error::InvalidConfig(e.to_string()))?
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::Headers;
+    use indoc::indoc;
+
+    #[test]
+    fn load_toml_config() {
+        let registry = VariantRegistry::from_toml(indoc! {r#"
+            [[variant]]
+            vendor = "Acme"
+            window = 60
+            hosts = ["api.acme.com"]
+            limit_header = "Acme-Limit"
+            remaining_header = "Acme-Remaining"
+            reset_header = "Acme-Reset"
+        "#})
+        .unwrap();
+        assert_eq!(registry.len(), 1);
+        assert!(registry.iter().next().unwrap().matches_host("api.acme.com"));
+
+        let headers = "Acme-Limit: 10\nAcme-Remaining: 5\nAcme-Reset: 30";
+        let rate = Headers::with_registry(headers, &registry).unwrap();
+        assert_eq!(rate.vendor, Vendor::Custom("Acme".to_string()));
+        assert_eq!(rate.window, Some(Duration::MINUTE));
+        assert!(Headers::with_registry("x-ratelimit-remaining: 1", &registry).is_err());
+    }
+
+    #[test]
+    fn load_json_config_with_defaults() {
+        let registry = VariantRegistry::from_json(
+            r#"{
+                "defaults": true,
+                "variant": [{
+                    "vendor": "github",
+                    "used_header": "x-ratelimit-used",
+                    "remaining_header": "x-ratelimit-remaining",
+                    "reset_header": "x-ratelimit-reset",
+                    "reset_kind": "seconds"
+                }]
+            }"#,
+        )
+        .unwrap();
+        let default = VariantRegistry::default();
+        assert_eq!(registry.len(), default.len());
+        assert_eq!(registry.iter().next().unwrap().vendor, Vendor::Github);
+        assert_eq!(
+            registry
+                .iter()
+                .filter(|v| v.vendor == Vendor::Github)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn reject_invalid_config() {
+        let unknown_kind = indoc! {r#"
+            [[variant]]
+            vendor = "Acme"
+            limit_header = "Acme-Limit"
+            remaining_header = "Acme-Remaining"
+            reset_header = "Acme-Reset"
+            reset_kind = "fortnights"
+        "#};
+        assert!(matches!(
+            VariantRegistry::from_toml(unknown_kind),
+            Err(Error::UnknownResetKind(kind)) if kind == "fortnights"
+        ));
+
+        let missing_remaining = r#"{"variant": [{
+            "vendor": "Acme",
+            "limit_header": "Acme-Limit",
+            "reset_header": "Acme-Reset"
+        }]}"#;
+        assert!(matches!(
+            VariantRegistry::from_json(missing_remaining),
+            Err(Error::IncompleteVariant("remaining"))
+        ));
+
+        assert!(matches!(
+            VariantRegistry::from_toml("[[variant]]\nvendor = 1"),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+}
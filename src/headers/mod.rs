@@ -1,39 +1,67 @@
 //! Rate limit headers as defined in [RFC 6585](https://tools.ietf.org/html/rfc6585)
 //! and [draft-polli-ratelimit-headers-00][draft].
+mod candidate;
+#[cfg(feature = "config")]
+mod config;
+mod registry;
 mod types;
 mod variants;
 
 use std::str::FromStr;
 
 use crate::{
-    casesensitive_headermap::CaseSensitiveHeaderMap,
+    casesensitive_headermap::{combine, singleton, CaseSensitiveHeaderMap, TryIntoHeaderMap},
+    clock::{Clock, SystemClock},
+    ietf::RateLimitPolicy,
     reset_time::{ResetTime, ResetTimeKind},
 };
 
-use super::error::{Error, Result};
-use headers::HeaderValue;
-use variants::RATE_LIMIT_HEADERS;
+use super::error::{Error, Result, Warning};
+use candidate::{candidates, Candidate};
+pub(crate) use variants::RATE_LIMIT_HEADERS;
 
-use time::Duration;
+pub use candidate::{MatchMode, VariantMatch};
+pub use registry::VariantRegistry;
+use time::{Duration, OffsetDateTime};
 use types::Used;
-pub use types::Vendor;
-pub(crate) use types::{Limit, RateLimitVariant, Remaining};
+pub(crate) use types::{Limit, Remaining};
+pub use types::{QuotaPolicy, RateLimitVariant, RateLimitVariantBuilder, Vendor};
 
 /// HTTP rate limits as parsed from header values
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Headers {
     /// The maximum number of requests allowed in the time window
     pub limit: usize,
+    /// Quota policies declared in the limit header, e.g. `1000;w=3600`
+    pub(crate) policies: Vec<QuotaPolicy>,
     /// The number of requests remaining in the time window
     pub remaining: usize,
-    /// The time at which the rate limit will be reset
-    pub reset: ResetTime,
+    /// The time at which the rate limit will be reset.
+    /// It is `None` if the vendor does not send a reset time.
+    pub reset: Option<ResetTime>,
+    /// The kind the reset value was parsed as.
+    ///
+    /// This is the kind of the vendor, unless it is [`ResetTimeKind::Auto`].
+    /// Then it is detected from the value, see [`ResetTimeKind::detect`].
+    pub(crate) reset_kind: Option<ResetTimeKind>,
     /// The time window until the rate limit is lifted.
     /// It is optional, because it might not be given,
     /// in which case it needs to be inferred from the environment
     pub window: Option<Duration>,
     /// Predicted vendor based on rate limit header
     pub vendor: Vendor,
+    /// Confidence in the predicted vendor between 0 and 1.
+    ///
+    /// Header names may match several vendors, e.g. when their casing
+    /// was lost. The vendor is chosen by how well the header values fit
+    /// the vendor, in particular the format of the reset time, and by
+    /// the casing of the header names.
+    pub(crate) confidence: f32,
+    /// Problems found while parsing, e.g. conflicting values
+    /// of a repeated header
+    pub(crate) warnings: Vec<Warning>,
+    /// Estimated offset of the server clock from the local clock
+    pub(crate) skew: Option<Duration>,
 }
 
 impl Headers {
@@ -48,109 +76,228 @@ impl Headers {
     ///
     /// This function returns an error if the given header map does not contain
     /// all required headers or if the header values cannot be parsed.
-    pub fn new<T: Into<CaseSensitiveHeaderMap>>(headers: T) -> std::result::Result<Self, Error> {
-        let headers = headers.into();
-        let value = Self::get_remaining(&headers)?;
-        let remaining = Remaining::new(value.to_str()?)?;
-
-        let (limit, variant) = if let Ok((limit, variant)) = Self::get_rate_limit(&headers) {
-            (Limit::new(limit.to_str()?)?, variant)
-        } else if let Ok((used, variant)) = Self::get_used(&headers) {
+    pub fn new<T: TryIntoHeaderMap>(headers: T) -> std::result::Result<Self, Error> {
+        Self::with_registry(headers, &RATE_LIMIT_HEADERS)
+    }
+
+    /// Extracts rate limits from `Rate-Limit-...` HTTP headers
+    /// of a response to a request to the given URL.
+    ///
+    /// Several vendors share header names, e.g. `X-RateLimit-Limit`
+    /// is sent by Vimeo and Akamai. The variants of the vendor known to
+    /// use the host of the URL are tried first, so the correct window and
+    /// reset time format are used. Other variants are tried afterwards.
+    ///
+    /// ```
+    /// use rate_limits::{Headers, Vendor};
+    ///
+    /// let headers = "RateLimit-Limit: 60\nRateLimit-Remaining: 59\nRateLimit-Reset: 1609844400";
+    /// let rate = Headers::with_host(headers, "https://gitlab.com/api/v4/projects").unwrap();
+    /// assert_eq!(rate.vendor, Vendor::Gitlab);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the given header map does not contain
+    /// all required headers or if the header values cannot be parsed.
+    pub fn with_host<T: TryIntoHeaderMap>(
+        headers: T,
+        url: &str,
+    ) -> std::result::Result<Self, Error> {
+        Self::with_registry(headers, &RATE_LIMIT_HEADERS.for_host(url))
+    }
+
+    /// Extracts rate limits from `Rate-Limit-...` HTTP headers,
+    /// consulting the variants of the given registry in order.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the given header map does not contain
+    /// all required headers or if the header values cannot be parsed.
+    pub fn with_registry<T: TryIntoHeaderMap>(
+        headers: T,
+        registry: &VariantRegistry,
+    ) -> std::result::Result<Self, Error> {
+        Self::parse_at(&headers.try_into_header_map()?, registry, SystemClock.now())
+    }
+
+    /// Extracts rate limits with the best ranked variant of the registry,
+    /// detecting the format of reset values relative to `now`.
+    ///
+    /// Variants whose reset format is contradicted by the value are skipped,
+    /// because the reset time would be off by decades.
+    pub(crate) fn parse_at(
+        headers: &CaseSensitiveHeaderMap,
+        registry: &VariantRegistry,
+        now: OffsetDateTime,
+    ) -> Result<Self> {
+        let mut candidates = candidates(headers, registry, now)?;
+        match candidates
+            .iter()
+            .position(|candidate| !candidate.contradicted)
+        {
+            Some(index) => Self::from_candidate(headers, candidates.swap_remove(index), now),
+            None => Err(candidates[0].mismatched_reset()),
+        }
+    }
+
+    /// Extracts the rate limits of every variant of the registry
+    /// whose headers are present.
+    ///
+    /// Variants which read the same remaining header, e.g. Vimeo and Akamai,
+    /// are alternatives for the same limit, so only the best ranked one
+    /// is kept. Variants with distinct headers, like the request and token
+    /// limits of OpenAI, are independent limits.
+    pub(crate) fn all_at(
+        headers: &CaseSensitiveHeaderMap,
+        registry: &VariantRegistry,
+        now: OffsetDateTime,
+    ) -> Result<Vec<Self>> {
+        let mut seen = Vec::new();
+        let mut all = Vec::new();
+        let mut error = None;
+        for candidate in candidates(headers, registry, now)? {
+            let remaining = candidate.variant.remaining_header.to_ascii_lowercase();
+            if seen.contains(&remaining) {
+                continue;
+            }
+            if candidate.contradicted {
+                error.get_or_insert_with(|| candidate.mismatched_reset());
+                continue;
+            }
+            match Self::from_candidate(headers, candidate, now) {
+                Ok(rate) => {
+                    seen.push(remaining);
+                    all.push(rate);
+                }
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        match error {
+            Some(e) if all.is_empty() => Err(e),
+            _ => Ok(all),
+        }
+    }
+
+    /// Parse the header values of a matching variant
+    fn from_candidate(
+        headers: &CaseSensitiveHeaderMap,
+        candidate: Candidate<'_>,
+        now: OffsetDateTime,
+    ) -> Result<Self> {
+        let variant = candidate.variant;
+        let mut warnings = candidate.warnings.clone();
+        let mut first = |name: &str, values| singleton(name, values, &mut warnings);
+
+        let remaining = first(&variant.remaining_header, &candidate.remaining)
+            .ok_or(Error::MissingRemaining)?;
+        let remaining = Remaining::new(remaining.to_str()?)?;
+
+        // The limit header may contain a list of quota policies,
+        // so repeated values are combined
+        let limit = if let Some(limit) = candidate.limit.as_deref().and_then(combine) {
+            Limit::new(limit.to_str()?)?
+        } else if let (Some(name), Some(used)) = (&variant.used_header, candidate.used.as_deref()) {
             // The site provides a `used` header, but no `limit` header.
             // Therefore we have to calculate the limit from used and remaining.
+            let used = first(name, used).ok_or(Error::MissingUsed)?;
             let used = Used::new(used.to_str()?)?;
-            let limit = used.count + remaining.count;
-            (Limit::from(limit), variant)
+            Limit::from(used.count + remaining.count)
         } else {
             return Err(Error::MissingUsed);
         };
 
-        let (value, kind) = Self::get_reset(&headers)?;
-        let reset = ResetTime::new(value, kind)?;
+        // Without a reset header, the reset time is unknown. It is not
+        // necessarily one window after the response, e.g. daily limits of
+        // HubSpot are reset at midnight.
+        let (reset, reset_kind) = match (&variant.reset_header, candidate.reset.as_deref()) {
+            (Some(name), Some(reset)) => {
+                let reset = first(name, reset).ok_or(Error::MissingReset)?;
+                let (reset, kind) = match variant.reset_kind {
+                    ResetTimeKind::Auto => ResetTime::detect_at(reset, now)?,
+                    kind => (ResetTime::new(reset, kind)?, kind),
+                };
+                (Some(reset), Some(kind))
+            }
+            _ => (None, None),
+        };
+
+        // Prefer the window declared by the server over the vendor default
+        let window = limit
+            .policy()
+            .map(|policy| policy.window)
+            .or_else(|| {
+                RateLimitPolicy::from_header_map(headers)
+                    .ok()
+                    .and_then(|policy| policy.for_limit(limit.count).and_then(|p| p.window))
+            })
+            .or(variant.duration);
 
         Ok(Headers {
             limit: limit.count,
+            policies: limit.policies,
             remaining: remaining.count,
             reset,
-            window: variant.duration,
-            vendor: variant.vendor,
+            reset_kind,
+            window,
+            vendor: variant.vendor.clone(),
+            confidence: candidate.confidence,
+            warnings,
+            skew: None,
         })
     }
 
     /// Get the number of requests allowed in the time window
-    /// from the given header map
-    fn get_rate_limit(
-        header_map: &CaseSensitiveHeaderMap,
-    ) -> Result<(&HeaderValue, RateLimitVariant)> {
-        let variants = &RATE_LIMIT_HEADERS;
-
-        for variant in variants.iter() {
-            if let Some(limit) = &variant.limit_header {
-                if let Some(value) = header_map.get(limit) {
-                    return Ok((value, variant.clone()));
-                }
-            }
-        }
-        Err(Error::MissingLimit)
+    #[must_use]
+    pub const fn limit(&self) -> usize {
+        self.limit
     }
 
-    /// Get the number of requests used in the time window
-    /// from the given header map
-    fn get_used(header_map: &CaseSensitiveHeaderMap) -> Result<(&HeaderValue, RateLimitVariant)> {
-        let variants = &RATE_LIMIT_HEADERS;
-
-        for variant in variants.iter() {
-            if let Some(used) = &variant.used_header {
-                if let Some(value) = header_map.get(used) {
-                    return Ok((value, variant.clone()));
-                }
-            }
-        }
-        Err(Error::MissingUsed)
+    /// Get the quota policies declared in the limit header
+    #[must_use]
+    pub fn policies(&self) -> &[QuotaPolicy] {
+        &self.policies
     }
 
     /// Get the number of requests remaining in the time window
-    /// from the given header map
-    fn get_remaining(header_map: &CaseSensitiveHeaderMap) -> Result<&HeaderValue> {
-        let variants = &RATE_LIMIT_HEADERS;
-
-        for variant in variants.iter() {
-            if let Some(value) = header_map.get(&variant.remaining_header) {
-                return Ok(value);
-            }
-        }
-        Err(Error::MissingRemaining)
+    #[must_use]
+    pub const fn remaining(&self) -> usize {
+        self.remaining
     }
 
-    /// Get the time at which the rate limit will be reset
-    /// from the given header map
-    fn get_reset(header_map: &CaseSensitiveHeaderMap) -> Result<(&HeaderValue, ResetTimeKind)> {
-        let variants = &RATE_LIMIT_HEADERS;
+    /// Get the time at which the rate limit will be reset,
+    /// if the vendor sends it
+    #[must_use]
+    pub const fn reset(&self) -> Option<ResetTime> {
+        self.reset
+    }
 
-        for variant in variants.iter() {
-            if let Some(value) = header_map.get(&variant.reset_header) {
-                return Ok((value, variant.reset_kind));
-            }
-        }
-        Err(Error::MissingReset)
+    /// Get the kind the reset value was parsed as,
+    /// if the vendor sends a reset time
+    #[must_use]
+    pub const fn reset_kind(&self) -> Option<ResetTimeKind> {
+        self.reset_kind
     }
 
-    /// Get the number of requests allowed in the time window
+    /// Get the confidence in the predicted vendor between 0 and 1
     #[must_use]
-    pub const fn limit(&self) -> usize {
-        self.limit
+    pub const fn confidence(&self) -> f32 {
+        self.confidence
     }
 
-    /// Get the number of requests remaining in the time window
+    /// Get the problems found while parsing
     #[must_use]
-    pub const fn remaining(&self) -> usize {
-        self.remaining
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
     }
 
-    /// Get the time at which the rate limit will be reset
+    /// Get the estimated offset of the server clock from the local clock,
+    /// see [`Anchor::skew`](crate::Anchor::skew)
     #[must_use]
-    pub const fn reset(&self) -> ResetTime {
-        self.reset
+    pub const fn skew(&self) -> Option<Duration> {
+        self.skew
     }
 }
 
@@ -165,10 +312,16 @@ impl FromStr for Headers {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::casesensitive_headermap::HeaderMapExt;
-    use headers::HeaderMap;
+    use crate::casesensitive_headermap::{HeaderMapExt, TryIntoHeaderMap};
+    use headers::{HeaderMap, HeaderValue};
     use indoc::indoc;
-    use time::{macros::datetime, OffsetDateTime};
+    use time::macros::datetime;
+
+    fn ignore_case() -> VariantRegistry {
+        let mut registry = VariantRegistry::default();
+        registry.set_match_mode(MatchMode::IgnoreCase);
+        registry
+    }
 
     #[test]
     fn parse_limit_value() {
@@ -183,15 +336,270 @@ mod tests {
         assert!(Limit::new("bar 0").is_err());
     }
 
+    #[test]
+    fn parse_limit_quota_policies() {
+        let limit = Limit::new("10, 10;w=1, 1000;w=3600").unwrap();
+        assert_eq!(limit.count, 10);
+        assert_eq!(
+            limit.policies,
+            vec![
+                QuotaPolicy {
+                    quota: 10,
+                    window: Duration::SECOND
+                },
+                QuotaPolicy {
+                    quota: 1000,
+                    window: Duration::HOUR
+                }
+            ]
+        );
+        assert_eq!(limit.policy().unwrap().window, Duration::SECOND);
+
+        let limit = Limit::new("100;w=21600").unwrap();
+        assert_eq!(limit.count, 100);
+        assert_eq!(limit.policy().unwrap().window, Duration::hours(6));
+
+        assert!(Limit::new("10, foo").is_err());
+        assert!(Limit::new("10, 10;w=-1").is_err());
+        assert!(Limit::new("").is_err());
+    }
+
     #[test]
     fn parse_vendor() {
-        let map = CaseSensitiveHeaderMap::from_str("x-ratelimit-limit: 5000").unwrap();
-        let (_, variant) = Headers::get_rate_limit(&map).unwrap();
-        assert_eq!(variant.vendor, Vendor::Github);
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Github);
+        assert_eq!(rate.confidence, 1.0);
 
-        let map = CaseSensitiveHeaderMap::from_str("RateLimit-Limit: 5000").unwrap();
-        let (_, variant) = Headers::get_rate_limit(&map).unwrap();
-        assert_eq!(variant.vendor, Vendor::Standard);
+        let headers = indoc! {"
+            RateLimit-Limit: 5000
+            Ratelimit-Remaining: 4987
+            Ratelimit-Reset: 30
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Standard);
+        assert_eq!(rate.confidence, 1.0);
+    }
+
+    #[test]
+    fn parse_vendor_by_value() {
+        // The names match the standard headers, but the reset value
+        // is a timestamp as sent by Gitlab
+        let headers = indoc! {"
+            RateLimit-Limit: 60
+            Ratelimit-Remaining: 59
+            Ratelimit-Reset: 1609844400
+        "};
+        let rate = Headers::with_registry(headers, &ignore_case()).unwrap();
+        assert_eq!(rate.vendor, Vendor::Gitlab);
+        assert_eq!(
+            rate.reset,
+            Some(ResetTime::DateTime(datetime!(2021-01-05 11:00:00 UTC)))
+        );
+        assert!(rate.confidence > 0.5 && rate.confidence < 1.0);
+
+        // Without a matching vendor, the timestamp is not read as seconds
+        let registry = RATE_LIMIT_HEADERS
+            .iter()
+            .filter(|variant| variant.vendor == Vendor::Standard)
+            .cloned()
+            .collect();
+        assert!(matches!(
+            Headers::with_registry(headers, &registry),
+            Err(Error::MismatchedReset(value)) if value == "1609844400"
+        ));
+    }
+
+    #[test]
+    fn detect_reset_kind_of_auto_variants() {
+        let headers = indoc! {"
+            X-Quota-Limit: 10
+            X-Quota-Remaining: 5
+            X-Quota-Reset: 1000
+        "}
+        .try_into_header_map()
+        .unwrap();
+        let registry: VariantRegistry = [RateLimitVariant::builder(Vendor::Standard)
+            .limit_header("X-Quota-Limit")
+            .remaining_header("X-Quota-Remaining")
+            .reset_header("X-Quota-Reset")
+            .reset_kind(ResetTimeKind::Auto)
+            .build()
+            .unwrap()]
+        .into_iter()
+        .collect();
+
+        // The kind is detected relative to the given time, not the system clock
+        let rate = Headers::parse_at(&headers, &registry, datetime!(1970-01-01 00:10 UTC)).unwrap();
+        assert_eq!(
+            rate.reset,
+            Some(ResetTime::DateTime(datetime!(1970-01-01 00:16:40 UTC)))
+        );
+        assert_eq!(rate.reset_kind, Some(ResetTimeKind::Timestamp));
+
+        let rate = Headers::parse_at(&headers, &registry, datetime!(2023-03-01 10:00 UTC)).unwrap();
+        assert_eq!(rate.reset, Some(ResetTime::Seconds(1000)));
+        assert_eq!(rate.reset_kind, Some(ResetTimeKind::Seconds));
+    }
+
+    #[test]
+    fn parse_vendor_from_lowercase_header_map() {
+        let mut headers = HeaderMap::new();
+        headers.insert("RateLimit-Limit", HeaderValue::from_static("60"));
+        headers.insert("RateLimit-Remaining", HeaderValue::from_static("59"));
+        headers.insert("RateLimit-Reset", HeaderValue::from_static("1609844400"));
+        assert!(matches!(
+            Headers::new(&headers),
+            Err(Error::MissingRemaining)
+        ));
+        let rate = Headers::with_registry(&headers, &ignore_case()).unwrap();
+        assert_eq!(rate.vendor, Vendor::Gitlab);
+        assert_eq!(rate.confidence, 1.0);
+
+        headers.insert("RateLimit-Reset", HeaderValue::from_static("30"));
+        let rate = Headers::with_registry(&headers, &ignore_case()).unwrap();
+        assert_eq!(rate.vendor, Vendor::Standard);
+        assert_eq!(rate.confidence, 1.0);
+    }
+
+    #[test]
+    fn parse_repeated_headers() {
+        let headers = indoc! {"
+            RateLimit-Limit: 10
+            RateLimit-Limit: 10;w=1, 1000;w=3600
+            Ratelimit-Remaining: 5
+            Ratelimit-Remaining: 4
+            Ratelimit-Reset: 30
+            Ratelimit-Reset: 30
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.limit, 10);
+        assert_eq!(rate.policies.len(), 2);
+        assert_eq!(rate.remaining, 5);
+        assert_eq!(rate.reset, Some(ResetTime::Seconds(30)));
+        assert_eq!(
+            rate.warnings,
+            vec![Warning::ConflictingValues(
+                "Ratelimit-Remaining".to_string(),
+                vec!["5".to_string(), "4".to_string()]
+            )]
+        );
+    }
+
+    #[test]
+    fn merge_header_casings() {
+        let headers = indoc! {"
+            ratelimit-limit: 10
+            RateLimit-Limit: 10
+            ratelimit-remaining: 4
+            Ratelimit-Remaining: 5
+            Ratelimit-Reset: 30
+        "};
+        // The result does not depend on the order of the header map
+        for _ in 0..10 {
+            let rate = Headers::with_registry(headers, &ignore_case()).unwrap();
+            assert_eq!(rate.limit, 10);
+            assert_eq!(rate.remaining, 5);
+            assert_eq!(
+                rate.warnings,
+                vec![
+                    Warning::ConflictingCasings(
+                        "Ratelimit-Remaining".to_string(),
+                        vec![
+                            "Ratelimit-Remaining".to_string(),
+                            "ratelimit-remaining".to_string()
+                        ]
+                    ),
+                    Warning::ConflictingValues(
+                        "Ratelimit-Remaining".to_string(),
+                        vec!["5".to_string(), "4".to_string()]
+                    )
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn parse_vendor_by_host() {
+        let headers = indoc! {"
+            X-RateLimit-Limit: 60
+            X-RateLimit-Remaining: 59
+            X-RateLimit-Reset: Sat, 01 Jan 2022 00:00:00 GMT
+            X-RateLimit-Next: 2022-01-01T00:00:00Z
+        "};
+        let rate = Headers::with_host(headers, "https://api.vimeo.com/me").unwrap();
+        assert_eq!(rate.vendor, Vendor::Vimeo);
+        assert_eq!(
+            rate.reset,
+            Some(ResetTime::DateTime(datetime!(2022-01-01 0:00:00 UTC)))
+        );
+
+        let rate = Headers::with_host(headers, "akab-xyz.luna.akamaiapis.net").unwrap();
+        assert_eq!(rate.vendor, Vendor::Akamai);
+
+        // Fall back to the header names for unknown hosts
+        let rate = Headers::with_host(
+            "x-ratelimit-limit: 5000\nx-ratelimit-remaining: 4987\nx-ratelimit-reset: 1350085394",
+            "example.com",
+        )
+        .unwrap();
+        assert_eq!(rate.vendor, Vendor::Github);
+    }
+
+    #[test]
+    fn detect_reset_kind() {
+        let variant = RateLimitVariant::builder(Vendor::Custom("Acme".to_string()))
+            .limit_header("Acme-Limit")
+            .remaining_header("Acme-Remaining")
+            .reset_header("Acme-Reset")
+            .reset_kind(ResetTimeKind::Auto)
+            .build()
+            .unwrap();
+        let registry: VariantRegistry = [variant].into_iter().collect();
+
+        let headers = "Acme-Limit: 10\nAcme-Remaining: 5\nAcme-Reset: 6m0s";
+        let rate = Headers::with_registry(headers, &registry).unwrap();
+        assert_eq!(rate.reset_kind(), Some(ResetTimeKind::GoDuration));
+        assert_eq!(
+            rate.reset,
+            Some(ResetTime::Duration(std::time::Duration::from_secs(360)))
+        );
+        assert!((rate.confidence() - 1.0).abs() < f32::EPSILON);
+
+        let headers = "Acme-Limit: 10\nAcme-Remaining: 5\nAcme-Reset: soon";
+        assert!(matches!(
+            Headers::with_registry(headers, &registry),
+            Err(Error::UndetectableResetKind(_))
+        ));
+    }
+
+    #[test]
+    fn parse_reset_with_offset() {
+        let headers = indoc! {"
+            X-RateLimit-Limit: 60
+            X-RateLimit-Remaining: 59
+            X-RateLimit-Next: 2023-03-01T10:00:00+01:00
+        "};
+        let rate = Headers::with_host(headers, "akamaiapis.net").unwrap();
+        assert_eq!(
+            rate.reset,
+            Some(ResetTime::DateTime(datetime!(2023-03-01 9:00:00 UTC)))
+        );
+
+        let headers = indoc! {"
+            X-RateLimit-Limit: 60
+            X-RateLimit-Remaining: 59
+            X-RateLimit-Reset: Wed, 01 Mar 2023 10:00:00 -0500
+        "};
+        let rate = Headers::with_host(headers, "vimeo.com").unwrap();
+        assert_eq!(
+            rate.reset,
+            Some(ResetTime::DateTime(datetime!(2023-03-01 15:00:00 UTC)))
+        );
     }
 
     #[test]
@@ -273,7 +681,9 @@ x-ratelimit-reset: 1350085394
         assert_eq!(rate.remaining(), 4987);
         assert_eq!(
             rate.reset(),
-            ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1_350_085_394).unwrap())
+            Some(ResetTime::DateTime(
+                OffsetDateTime::from_unix_timestamp(1_350_085_394).unwrap()
+            ))
         );
     }
 
@@ -288,7 +698,34 @@ x-ratelimit-reset: 1350085394
         let rate = Headers::from_str(headers).unwrap();
         assert_eq!(rate.limit(), 122);
         assert_eq!(rate.remaining(), 22);
-        assert_eq!(rate.reset(), ResetTime::Seconds(30));
+        assert_eq!(rate.reset(), Some(ResetTime::Seconds(30)));
+    }
+
+    #[test]
+    fn parse_window_from_quota_policy() {
+        let headers = indoc! {"
+            RateLimit-Limit: 10, 10;w=1, 1000;w=3600
+            Ratelimit-Remaining: 5
+            Ratelimit-Reset: 1
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.limit(), 10);
+        assert_eq!(rate.policies().len(), 2);
+        assert_eq!(rate.window, Some(Duration::SECOND));
+    }
+
+    #[test]
+    fn parse_window_from_policy() {
+        let headers = indoc! {r#"
+            RateLimit-Limit: 100
+            Ratelimit-Remaining: 50
+            Ratelimit-Reset: 30
+            RateLimit-Policy: "burst";q=100;w=60,"daily";q=1000;w=86400
+        "#};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.window, Some(Duration::seconds(60)));
     }
 
     #[test]
@@ -305,7 +742,9 @@ x-ratelimit-reset: 1350085394
         assert_eq!(rate.remaining(), 0);
         assert_eq!(
             rate.reset(),
-            ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1_609_844_400).unwrap())
+            Some(ResetTime::DateTime(
+                OffsetDateTime::from_unix_timestamp(1_609_844_400).unwrap()
+            ))
         );
     }
 }
@@ -0,0 +1,284 @@
+use super::candidate::{candidates, MatchMode, VariantMatch};
+use super::types::{RateLimitVariant, Vendor};
+use super::variants::RATE_LIMIT_HEADERS;
+use crate::casesensitive_headermap::TryIntoHeaderMap;
+use crate::clock::{Clock, SystemClock};
+use crate::error::{Error, Result};
+
+/// An ordered collection of rate limit variants
+///
+/// Variants are checked in order when parsing headers, so earlier variants
+/// take precedence if the header names of multiple variants match.
+/// The default registry contains all built-in vendors
+/// and matches header names case-sensitively, see [`MatchMode`].
+///
+/// ```
+/// use rate_limits::headers::{Headers, RateLimitVariant, VariantRegistry};
+/// use rate_limits::Vendor;
+///
+/// let acme = RateLimitVariant::builder(Vendor::Custom("Acme".to_string()))
+///     .limit_header("Acme-Limit")
+///     .remaining_header("Acme-Remaining")
+///     .reset_header("Acme-Reset")
+///     .build()
+///     .unwrap();
+///
+/// let mut registry = VariantRegistry::default();
+/// registry.push(acme);
+///
+/// let headers = "Acme-Limit: 10\nAcme-Remaining: 5\nAcme-Reset: 30";
+/// let rate = Headers::with_registry(headers, &registry).unwrap();
+/// assert_eq!(rate.vendor, Vendor::Custom("Acme".to_string()));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariantRegistry {
+    variants: Vec<RateLimitVariant>,
+    match_mode: MatchMode,
+    /// Host of the request, see [`VariantRegistry::for_host`]
+    host: Option<String>,
+}
+
+impl Default for VariantRegistry {
+    fn default() -> Self {
+        RATE_LIMIT_HEADERS.clone()
+    }
+}
+
+impl VariantRegistry {
+    /// Create an empty registry without any variants
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            variants: Vec::new(),
+            match_mode: MatchMode::CaseSensitive,
+            host: None,
+        }
+    }
+
+    /// Get how header names are matched
+    #[must_use]
+    pub const fn match_mode(&self) -> MatchMode {
+        self.match_mode
+    }
+
+    /// Set how header names are matched
+    pub fn set_match_mode(&mut self, mode: MatchMode) {
+        self.match_mode = mode;
+    }
+
+    /// Get every variant whose headers are present in the given headers.
+    ///
+    /// Variants known to be sent by the host of [`VariantRegistry::for_host`]
+    /// rank first. Otherwise, the variants are ranked by the number of their
+    /// headers which are present, then by confidence. The first match is the
+    /// one used by
+    /// [`Headers::with_registry`](super::Headers::with_registry).
+    /// More than one match means that the headers are ambiguous.
+    ///
+    /// ```
+    /// use rate_limits::headers::{MatchMode, VariantRegistry};
+    /// use rate_limits::Vendor;
+    ///
+    /// let mut registry = VariantRegistry::default();
+    /// registry.set_match_mode(MatchMode::IgnoreCase);
+    ///
+    /// let headers = "x-ratelimit-limit: 60\nx-ratelimit-remaining: 59\nx-ratelimit-reset: 1350085394";
+    /// let matches = registry.candidates(headers).unwrap();
+    /// assert_eq!(matches[0].variant.vendor(), &Vendor::Github);
+    /// assert_eq!(matches[1].variant.vendor(), &Vendor::Vimeo);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the headers cannot be parsed.
+    /// No matching variant results in an empty list.
+    pub fn candidates<T: TryIntoHeaderMap>(&self, headers: T) -> Result<Vec<VariantMatch>> {
+        match candidates(&headers.try_into_header_map()?, self, SystemClock.now()) {
+            Ok(candidates) => Ok(candidates.into_iter().map(VariantMatch::from).collect()),
+            Err(Error::MissingRemaining | Error::MissingUsed | Error::MissingReset) => {
+                Ok(Vec::new())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Add a variant with the lowest precedence
+    pub fn push(&mut self, variant: RateLimitVariant) {
+        self.variants.push(variant);
+    }
+
+    /// Add a variant at the given position
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of variants.
+    pub fn insert(&mut self, index: usize, variant: RateLimitVariant) {
+        self.variants.insert(index, variant);
+    }
+
+    /// Remove all variants of the given vendor
+    pub fn remove(&mut self, vendor: &Vendor) {
+        self.variants.retain(|variant| variant.vendor != *vendor);
+    }
+
+    /// Move all variants of the given vendor to the front,
+    /// so they take precedence over all others
+    pub fn prioritize(&mut self, vendor: &Vendor) {
+        // `sort_by_key` is stable, so the order within both groups is kept
+        self.variants
+            .sort_by_key(|variant| variant.vendor != *vendor);
+    }
+
+    /// Get a copy of the registry in which the variants known to be sent
+    /// by the host of the given URL take precedence.
+    ///
+    /// Both URLs like `https://api.github.com/user` and bare hosts like
+    /// `api.github.com` are accepted. Matching variants are chosen even if
+    /// another variant has more of its headers present or fits the values
+    /// better. All other variants are kept as a fallback in their original
+    /// order.
+    #[must_use]
+    pub fn for_host(&self, url: &str) -> Self {
+        let mut registry = self.clone();
+        registry.host = host(url);
+        if let Some(host) = &registry.host {
+            registry
+                .variants
+                .sort_by_key(|variant| !variant.matches_host(host));
+        }
+        registry
+    }
+
+    /// Whether the variant is known to be sent by the host of the request
+    pub(crate) fn matches_host(&self, variant: &RateLimitVariant) -> bool {
+        self.host
+            .as_deref()
+            .is_some_and(|host| variant.matches_host(host))
+    }
+
+    /// Iterate over all variants in order
+    pub fn iter(&self) -> std::slice::Iter<'_, RateLimitVariant> {
+        self.variants.iter()
+    }
+
+    /// Number of variants
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    /// Whether the registry contains no variants
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+}
+
+/// Extract the host from a URL or a bare host name
+fn host(url: &str) -> Option<String> {
+    let url = url.trim();
+    match url.parse::<http::Uri>() {
+        Ok(uri) => uri.host().map(ToString::to_string),
+        // e.g. a host with a path but without a scheme
+        Err(_) => url
+            .split(['/', '?', '#'])
+            .next()
+            .filter(|host| !host.is_empty())
+            .map(|host| host.rsplit('@').next().unwrap_or(host))
+            .map(|host| host.split(':').next().unwrap_or(host).to_string()),
+    }
+}
+
+impl FromIterator<RateLimitVariant> for VariantRegistry {
+    fn from_iter<I: IntoIterator<Item = RateLimitVariant>>(iter: I) -> Self {
+        Self {
+            variants: iter.into_iter().collect(),
+            match_mode: MatchMode::default(),
+            host: None,
+        }
+    }
+}
+
+impl Extend<RateLimitVariant> for VariantRegistry {
+    fn extend<I: IntoIterator<Item = RateLimitVariant>>(&mut self, iter: I) {
+        self.variants.extend(iter);
+    }
+}
+
+impl<'a> IntoIterator for &'a VariantRegistry {
+    type Item = &'a RateLimitVariant;
+    type IntoIter = std::slice::Iter<'a, RateLimitVariant>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(name: &str) -> RateLimitVariant {
+        RateLimitVariant::builder(Vendor::Custom(name.to_string()))
+            .limit_header(format!("{name}-Limit"))
+            .remaining_header(format!("{name}-Remaining"))
+            .reset_header(format!("{name}-Reset"))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn default_registry_contains_builtin_vendors() {
+        let registry = VariantRegistry::default();
+        assert_eq!(registry.iter().next().unwrap().vendor, Vendor::Standard);
+        assert!(registry.iter().any(|v| v.vendor == Vendor::Akamai));
+        assert!(VariantRegistry::new().is_empty());
+    }
+
+    #[test]
+    fn extend_and_reorder_registry() {
+        let mut registry = VariantRegistry::default();
+        let len = registry.len();
+        registry.push(custom("Acme"));
+        registry.insert(0, custom("First"));
+        assert_eq!(registry.len(), len + 2);
+        assert_eq!(
+            registry.iter().next().unwrap().vendor,
+            Vendor::Custom("First".to_string())
+        );
+
+        registry.prioritize(&Vendor::Custom("Acme".to_string()));
+        let vendors: Vec<_> = registry.iter().map(|v| v.vendor.clone()).collect();
+        assert_eq!(vendors[0], Vendor::Custom("Acme".to_string()));
+        assert_eq!(vendors[1], Vendor::Custom("First".to_string()));
+        assert_eq!(vendors[2], Vendor::Standard);
+
+        registry.remove(&Vendor::Github);
+        assert!(registry.iter().all(|v| v.vendor != Vendor::Github));
+    }
+
+    #[test]
+    fn prioritize_variants_by_host() {
+        let registry = VariantRegistry::default();
+        for (url, vendor) in [
+            (
+                "https://api.github.com/repos/mre/rate-limits",
+                Vendor::Github,
+            ),
+            ("gitlab.com", Vendor::Gitlab),
+            ("api.vimeo.com:443", Vendor::Vimeo),
+            ("API.Twitter.com/2/tweets", Vendor::Twitter),
+        ] {
+            let registry = registry.for_host(url);
+            assert_eq!(registry.iter().next().unwrap().vendor, vendor, "{url}");
+            assert_eq!(registry.len(), VariantRegistry::default().len());
+        }
+
+        // Unknown hosts and lookalike domains keep the default order
+        assert!(registry
+            .for_host("https://example.com")
+            .iter()
+            .eq(&registry));
+        assert!(registry.for_host("notgithub.com").iter().eq(&registry));
+    }
+}
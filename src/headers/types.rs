@@ -1,13 +1,14 @@
 use crate::convert;
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::reset_time::ResetTimeKind;
+use crate::structured::{self, ListEntry};
 use time::Duration;
 
 /// Known vendors of rate limit headers
 ///
 /// Vendors use different rate limit header formats,
 /// which define how to parse them.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Vendor {
     /// Rate limit headers as defined in the `polli-ratelimit-headers-00` draft
     Standard,
@@ -23,11 +24,36 @@ pub enum Vendor {
     Gitlab,
     /// Akamai rate limit headers
     Akamai,
+    /// OpenAI API rate limit headers for requests and tokens
+    OpenAi,
+    /// HubSpot API rate limit headers for the daily and secondly limits
+    Hubspot,
+    /// A vendor registered at runtime, identified by its name
+    Custom(String),
 }
 
 /// A variant defines all relevant fields for parsing headers from a given vendor
+///
+/// Custom variants can be created with [`RateLimitVariant::builder`]
+/// and added to a [`VariantRegistry`](super::VariantRegistry).
+///
+/// ```
+/// use rate_limits::headers::RateLimitVariant;
+/// use rate_limits::{ResetTimeKind, Vendor};
+/// use time::Duration;
+///
+/// let variant = RateLimitVariant::builder(Vendor::Custom("Acme".to_string()))
+///     .duration(Duration::minutes(1))
+///     .limit_header("Acme-Limit")
+///     .remaining_header("Acme-Remaining")
+///     .reset_header("Acme-Reset")
+///     .reset_kind(ResetTimeKind::Timestamp)
+///     .build()
+///     .unwrap();
+/// assert_eq!(variant.remaining_header(), "Acme-Remaining");
+/// ```
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct RateLimitVariant {
+pub struct RateLimitVariant {
     /// Vendor of the rate limit headers (e.g. Github, Twitter, etc.)
     pub(crate) vendor: Vendor,
     /// Duration of the rate limit interval
@@ -38,10 +64,12 @@ pub(crate) struct RateLimitVariant {
     pub(crate) used_header: Option<String>,
     /// Header name for the number of remaining requests
     pub(crate) remaining_header: String,
-    /// Header name for the reset time
-    pub(crate) reset_header: String,
+    /// Header name for the reset time, if the vendor sends it
+    pub(crate) reset_header: Option<String>,
     /// Kind of reset time
     pub(crate) reset_kind: ResetTimeKind,
+    /// Hosts which are known to send these headers, including subdomains
+    pub(crate) hosts: Vec<String>,
 }
 
 impl RateLimitVariant {
@@ -53,7 +81,7 @@ impl RateLimitVariant {
         limit_header: Option<String>,
         used_header: Option<String>,
         remaining_header: String,
-        reset_header: String,
+        reset_header: Option<String>,
         reset_kind: ResetTimeKind,
     ) -> Self {
         Self {
@@ -64,33 +92,272 @@ impl RateLimitVariant {
             remaining_header,
             reset_header,
             reset_kind,
+            hosts: Vec::new(),
+        }
+    }
+
+    /// Set the hosts which are known to send these headers
+    #[must_use]
+    pub(crate) fn with_hosts(mut self, hosts: &[&str]) -> Self {
+        self.hosts = hosts.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Create a builder for a rate limit variant of the given vendor
+    #[must_use]
+    pub const fn builder(vendor: Vendor) -> RateLimitVariantBuilder {
+        RateLimitVariantBuilder {
+            vendor,
+            duration: None,
+            limit_header: None,
+            used_header: None,
+            remaining_header: None,
+            reset_header: None,
+            reset_kind: ResetTimeKind::Seconds,
+            hosts: Vec::new(),
+        }
+    }
+
+    /// Get the vendor of the rate limit headers
+    #[must_use]
+    pub const fn vendor(&self) -> &Vendor {
+        &self.vendor
+    }
+
+    /// Get the duration of the rate limit interval
+    #[must_use]
+    pub const fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Get the header name for the maximum number of requests
+    #[must_use]
+    pub fn limit_header(&self) -> Option<&str> {
+        self.limit_header.as_deref()
+    }
+
+    /// Get the header name for the number of used requests
+    #[must_use]
+    pub fn used_header(&self) -> Option<&str> {
+        self.used_header.as_deref()
+    }
+
+    /// Get the header name for the number of remaining requests
+    #[must_use]
+    pub fn remaining_header(&self) -> &str {
+        &self.remaining_header
+    }
+
+    /// Get the header name for the reset time.
+    ///
+    /// The reset time of variants without a reset header is unknown,
+    /// only their duration is.
+    #[must_use]
+    pub fn reset_header(&self) -> Option<&str> {
+        self.reset_header.as_deref()
+    }
+
+    /// Get the kind of reset time
+    #[must_use]
+    pub const fn reset_kind(&self) -> ResetTimeKind {
+        self.reset_kind
+    }
+
+    /// Get the hosts which are known to send these headers
+    #[must_use]
+    pub fn hosts(&self) -> &[String] {
+        &self.hosts
+    }
+
+    /// Whether the given host or one of its parent domains
+    /// is known to send these headers
+    #[must_use]
+    pub fn matches_host(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.');
+        self.hosts.iter().any(|known| {
+            host.eq_ignore_ascii_case(known)
+                || host.len() > known.len()
+                    && host.as_bytes()[host.len() - known.len() - 1] == b'.'
+                    && host[host.len() - known.len()..].eq_ignore_ascii_case(known)
+        })
+    }
+}
+
+/// Builder for a [`RateLimitVariant`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimitVariantBuilder {
+    vendor: Vendor,
+    duration: Option<Duration>,
+    limit_header: Option<String>,
+    used_header: Option<String>,
+    remaining_header: Option<String>,
+    reset_header: Option<String>,
+    reset_kind: ResetTimeKind,
+    hosts: Vec<String>,
+}
+
+impl RateLimitVariantBuilder {
+    /// Set the duration of the rate limit interval
+    #[must_use]
+    pub const fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Set the header name for the maximum number of requests
+    #[must_use]
+    pub fn limit_header<T: Into<String>>(mut self, name: T) -> Self {
+        self.limit_header = Some(name.into());
+        self
+    }
+
+    /// Set the header name for the number of used requests.
+    ///
+    /// If there is no limit header, the limit is calculated
+    /// from the used and remaining requests.
+    #[must_use]
+    pub fn used_header<T: Into<String>>(mut self, name: T) -> Self {
+        self.used_header = Some(name.into());
+        self
+    }
+
+    /// Set the header name for the number of remaining requests
+    #[must_use]
+    pub fn remaining_header<T: Into<String>>(mut self, name: T) -> Self {
+        self.remaining_header = Some(name.into());
+        self
+    }
+
+    /// Set the header name for the reset time.
+    ///
+    /// If the vendor does not send the reset time, set the
+    /// [`duration`](Self::duration) instead. The reset time of the
+    /// limit is then unknown.
+    #[must_use]
+    pub fn reset_header<T: Into<String>>(mut self, name: T) -> Self {
+        self.reset_header = Some(name.into());
+        self
+    }
+
+    /// Set the kind of reset time (defaults to [`ResetTimeKind::Seconds`])
+    #[must_use]
+    pub const fn reset_kind(mut self, kind: ResetTimeKind) -> Self {
+        self.reset_kind = kind;
+        self
+    }
+
+    /// Add a host which is known to send these headers.
+    ///
+    /// Subdomains of the host match as well.
+    #[must_use]
+    pub fn host<T: Into<String>>(mut self, host: T) -> Self {
+        self.hosts.push(host.into());
+        self
+    }
+
+    /// Build the rate limit variant
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the remaining header name is
+    /// missing, if neither a limit nor a used header name is set, or if
+    /// neither a reset header name nor a duration is set.
+    pub fn build(self) -> Result<RateLimitVariant> {
+        if self.limit_header.is_none() && self.used_header.is_none() {
+            return Err(Error::IncompleteVariant("limit or used"));
         }
+        if self.reset_header.is_none() && self.duration.is_none() {
+            return Err(Error::IncompleteVariant("reset"));
+        }
+        Ok(RateLimitVariant {
+            vendor: self.vendor,
+            duration: self.duration,
+            limit_header: self.limit_header,
+            used_header: self.used_header,
+            remaining_header: self
+                .remaining_header
+                .ok_or(Error::IncompleteVariant("remaining"))?,
+            reset_header: self.reset_header,
+            reset_kind: self.reset_kind,
+            hosts: self.hosts,
+        })
     }
 }
 
+/// A quota policy as declared in the `RateLimit-Limit` header
+///
+/// For example, `1000;w=3600` declares a quota of 1000 requests per hour.
+///
+/// See <https://datatracker.ietf.org/doc/html/draft-polli-ratelimit-headers-00#section-3.1>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuotaPolicy {
+    /// Number of requests allowed in the time window
+    pub quota: usize,
+    /// Time window of the policy
+    pub window: Duration,
+}
+
 /// A rate limit header
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) struct Limit {
     /// Maximum number of requests for the given interval
     pub(crate) count: usize,
+    /// Quota policies declared alongside the limit
+    pub(crate) policies: Vec<QuotaPolicy>,
 }
 
 impl Limit {
     /// Create a new limit header
     ///
+    /// The value is the effective limit, optionally followed by quota
+    /// policies, e.g. `10, 10;w=1, 1000;w=3600`.
+    ///
     /// # Errors
     ///
     /// This function returns an error if the header value cannot be parsed
     pub(crate) fn new<T: AsRef<str>>(value: T) -> Result<Self> {
-        Ok(Self {
-            count: convert::to_usize(value.as_ref())?,
-        })
+        let value = value.as_ref();
+        let invalid = || Error::InvalidLimit(value.to_string());
+
+        let members = structured::parse_list(value)?
+            .iter()
+            .map(Self::quota_policy)
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+        let (count, _) = *members.first().ok_or_else(invalid)?;
+        let policies = members
+            .into_iter()
+            .filter_map(|(quota, window)| window.map(|window| QuotaPolicy { quota, window }))
+            .collect();
+
+        Ok(Self { count, policies })
+    }
+
+    /// Get the quota and the window (if any) of a list member
+    fn quota_policy(entry: &ListEntry) -> Option<(usize, Option<Duration>)> {
+        let item = entry.as_item()?;
+        let quota = usize::try_from(item.bare_item.as_integer()?).ok()?;
+        let window = match item.params.get("w") {
+            Some(w) => Some(Duration::seconds(w.as_integer().filter(|w| *w >= 0)?)),
+            None => None,
+        };
+        Some((quota, window))
+    }
+
+    /// Find the quota policy which matches the effective limit
+    pub(crate) fn policy(&self) -> Option<&QuotaPolicy> {
+        self.policies
+            .iter()
+            .find(|policy| policy.quota == self.count)
     }
 }
 
 impl From<usize> for Limit {
     fn from(count: usize) -> Self {
-        Self { count }
+        Self {
+            count,
+            policies: Vec::new(),
+        }
     }
 }
 
@@ -128,3 +395,40 @@ impl Remaining {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_incomplete_variant() {
+        let builder = RateLimitVariant::builder(Vendor::Custom("Acme".to_string()));
+        assert!(builder
+            .clone()
+            .limit_header("Limit")
+            .reset_header("Reset")
+            .build()
+            .is_err());
+        assert!(builder
+            .clone()
+            .limit_header("Limit")
+            .remaining_header("Remaining")
+            .build()
+            .is_err());
+        assert!(builder
+            .clone()
+            .remaining_header("Remaining")
+            .reset_header("Reset")
+            .build()
+            .is_err());
+
+        // The duration replaces a missing reset header
+        let variant = builder
+            .limit_header("Limit")
+            .remaining_header("Remaining")
+            .duration(Duration::DAY)
+            .build()
+            .unwrap();
+        assert_eq!(variant.reset_header(), None);
+    }
+}
@@ -2,15 +2,19 @@ use once_cell::sync::Lazy;
 
 use crate::reset_time::ResetTimeKind;
 
+use super::registry::VariantRegistry;
 use super::types::{RateLimitVariant, Vendor};
 use time::Duration;
 
 /// Different types of rate-limit headers
 ///
 /// Variants will be checked in order.
-/// The casing of header names is significant to separate between different
-/// vendors
-pub(crate) static RATE_LIMIT_HEADERS: Lazy<Vec<RateLimitVariant>> = Lazy::new(|| {
+/// Header names are matched case-sensitively by default, which separates
+/// vendors whose names only differ by their casing. Set
+/// [`MatchMode::IgnoreCase`](super::MatchMode::IgnoreCase) on the registry
+/// to match header names of any casing, e.g. from an `http::HeaderMap`.
+/// This is the default [`VariantRegistry`].
+pub(crate) static RATE_LIMIT_HEADERS: Lazy<VariantRegistry> = Lazy::new(|| {
     vec![
         // Headers as defined in https://tools.ietf.org/id/draft-polli-ratelimit-headers-00.html
         // RateLimit-Limit:     Holds the requests quota in the time window;
@@ -22,7 +26,7 @@ pub(crate) static RATE_LIMIT_HEADERS: Lazy<Vec<RateLimitVariant>> = Lazy::new(||
             Some("RateLimit-Limit".to_string()),
             None,
             "Ratelimit-Remaining".to_string(),
-            "Ratelimit-Reset".to_string(),
+            Some("Ratelimit-Reset".to_string()),
             ResetTimeKind::Seconds,
         ),
         // Reddit (https://www.reddit.com/r/redditdev/comments/1yxrp7/formal_ratelimiting_headers/)
@@ -35,9 +39,10 @@ pub(crate) static RATE_LIMIT_HEADERS: Lazy<Vec<RateLimitVariant>> = Lazy::new(||
             None,
             Some("X-Ratelimit-Used".to_string()),
             "X-Ratelimit-Remaining".to_string(),
-            "X-Ratelimit-Reset".to_string(),
+            Some("X-Ratelimit-Reset".to_string()),
             ResetTimeKind::Seconds,
-        ),
+        )
+        .with_hosts(&["reddit.com"]),
         // Github (https://docs.github.com/en/rest/overview/resources-in-the-rest-api#rate-limit-http-headers)
         // x-ratelimit-limit	    The maximum number of requests you're permitted to make per hour.
         // x-ratelimit-remaining	The number of requests remaining in the current rate limit window.
@@ -48,9 +53,10 @@ pub(crate) static RATE_LIMIT_HEADERS: Lazy<Vec<RateLimitVariant>> = Lazy::new(||
             Some("x-ratelimit-limit".to_string()),
             None,
             "x-ratelimit-remaining".to_string(),
-            "x-ratelimit-reset".to_string(),
+            Some("x-ratelimit-reset".to_string()),
             ResetTimeKind::Timestamp,
-        ),
+        )
+        .with_hosts(&["github.com"]),
         // Twitter (https://developer.twitter.com/en/docs/twitter-api/rate-limits)
         // x-rate-limit-limit:      the rate limit ceiling for that given endpoint
         // x-rate-limit-remaining:  the number of requests left for the 15-minute window
@@ -61,9 +67,10 @@ pub(crate) static RATE_LIMIT_HEADERS: Lazy<Vec<RateLimitVariant>> = Lazy::new(||
             Some("x-rate-limit-limit".to_string()),
             None,
             "x-rate-limit-remaining".to_string(),
-            "x-rate-limit-reset".to_string(),
+            Some("x-rate-limit-reset".to_string()),
             ResetTimeKind::Timestamp,
-        ),
+        )
+        .with_hosts(&["twitter.com", "x.com"]),
         // Vimeo (https://developer.vimeo.com/guidelines/rate-limiting)
         // X-RateLimit-Limit	    The maximum number of API responses that the requester can make through your app in any given 60-second period.*
         // X-RateLimit-Remaining    The remaining number of API responses that the requester can make through your app in the current 60-second period.*
@@ -74,9 +81,10 @@ pub(crate) static RATE_LIMIT_HEADERS: Lazy<Vec<RateLimitVariant>> = Lazy::new(||
             Some("X-RateLimit-Limit".to_string()),
             None,
             "X-RateLimit-Remaining".to_string(),
-            "X-RateLimit-Reset".to_string(),
+            Some("X-RateLimit-Reset".to_string()),
             ResetTimeKind::ImfFixdate,
-        ),
+        )
+        .with_hosts(&["vimeo.com"]),
         // Gitlab (https://docs.gitlab.com/ee/user/admin_area/settings/user_and_ip_rate_limits.html#response-headers)
         // RateLimit-Limit:     The request quota for the client each minute.
         // RateLimit-Observed   Number of requests associated to the client in the time window.
@@ -88,9 +96,10 @@ pub(crate) static RATE_LIMIT_HEADERS: Lazy<Vec<RateLimitVariant>> = Lazy::new(||
             Some("RateLimit-Limit".to_string()),
             Some("RateLimit-Observed".to_string()),
             "RateLimit-Remaining".to_string(),
-            "RateLimit-Reset".to_string(),
+            Some("RateLimit-Reset".to_string()),
             ResetTimeKind::Timestamp,
-        ),
+        )
+        .with_hosts(&["gitlab.com"]),
         // Akamai (https://techdocs.akamai.com/adaptive-media-delivery/reference/rate-limiting)
         // X-RateLimit-Limit:       60 requests per minute.
         // X-RateLimit-Remaining:   Number of remaining requests allowed during the period.
@@ -101,8 +110,63 @@ pub(crate) static RATE_LIMIT_HEADERS: Lazy<Vec<RateLimitVariant>> = Lazy::new(||
             Some("X-RateLimit-Limit".to_string()),
             None,
             "X-RateLimit-Remaining".to_string(),
-            "X-RateLimit-Next".to_string(),
+            Some("X-RateLimit-Next".to_string()),
             ResetTimeKind::Iso8601,
-        ),
+        )
+        .with_hosts(&["akamaiapis.net"]),
+        // OpenAI (https://platform.openai.com/docs/guides/rate-limits)
+        // x-ratelimit-limit-requests:      The maximum number of requests that are permitted before exhausting the rate limit.
+        // x-ratelimit-remaining-requests:  The remaining number of requests that are permitted before exhausting the rate limit.
+        // x-ratelimit-reset-requests:      The time until the rate limit (based on requests) resets to its initial state, e.g. `1s` or `6m0s`.
+        // The same headers with a `-tokens` suffix hold the limit on tokens.
+        RateLimitVariant::new(
+            Vendor::OpenAi,
+            Some(Duration::MINUTE),
+            Some("x-ratelimit-limit-requests".to_string()),
+            None,
+            "x-ratelimit-remaining-requests".to_string(),
+            Some("x-ratelimit-reset-requests".to_string()),
+            ResetTimeKind::GoDuration,
+        )
+        .with_hosts(&["openai.com"]),
+        RateLimitVariant::new(
+            Vendor::OpenAi,
+            Some(Duration::MINUTE),
+            Some("x-ratelimit-limit-tokens".to_string()),
+            None,
+            "x-ratelimit-remaining-tokens".to_string(),
+            Some("x-ratelimit-reset-tokens".to_string()),
+            ResetTimeKind::GoDuration,
+        )
+        .with_hosts(&["openai.com"]),
+        // HubSpot (https://developers.hubspot.com/docs/api/usage-details)
+        // X-HubSpot-RateLimit-Daily:               The number of API requests that are allowed per day.
+        // X-HubSpot-RateLimit-Daily-Remaining:     The number of API requests still allowed for the current day.
+        // X-HubSpot-RateLimit-Secondly:            The number of API requests that are allowed per second.
+        // X-HubSpot-RateLimit-Secondly-Remaining:  The number of API requests still allowed for the current second.
+        // There is no reset header, so the reset time is unknown. The daily limit
+        // is reset at midnight, not one day after the response.
+        RateLimitVariant::new(
+            Vendor::Hubspot,
+            Some(Duration::DAY),
+            Some("X-HubSpot-RateLimit-Daily".to_string()),
+            None,
+            "X-HubSpot-RateLimit-Daily-Remaining".to_string(),
+            None,
+            ResetTimeKind::Seconds,
+        )
+        .with_hosts(&["hubapi.com", "hubspot.com"]),
+        RateLimitVariant::new(
+            Vendor::Hubspot,
+            Some(Duration::SECOND),
+            Some("X-HubSpot-RateLimit-Secondly".to_string()),
+            None,
+            "X-HubSpot-RateLimit-Secondly-Remaining".to_string(),
+            None,
+            ResetTimeKind::Seconds,
+        )
+        .with_hosts(&["hubapi.com", "hubspot.com"]),
     ]
+    .into_iter()
+    .collect()
 });
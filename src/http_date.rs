@@ -0,0 +1,153 @@
+//! Parsing of HTTP dates
+//!
+//! See <https://www.rfc-editor.org/rfc/rfc9110#name-date-time-formats>
+use time::format_description::well_known::Rfc2822;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::parsing::Parsed;
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::{Error, Result};
+
+/// Obsolete RFC 850 format, e.g. `Sunday, 06-Nov-94 08:49:37 GMT`
+const RFC850: &[FormatItem<'_>] = format_description!(
+    "[weekday], [day]-[month repr:short]-[year repr:last_two] [hour]:[minute]:[second] GMT"
+);
+
+/// ANSI C's `asctime()` format, e.g. `Sun Nov  6 08:49:37 1994`
+const ASCTIME: &[FormatItem<'_>] = format_description!(
+    "[weekday repr:short] [month repr:short] [day padding:space] [hour]:[minute]:[second] [year]"
+);
+
+/// Parse an HTTP date in any of the three formats recipients must accept:
+/// IMF-fixdate (or any other RFC 2822 date), RFC 850 and asctime.
+///
+/// Two-digit years of RFC 850 dates are resolved relative to the current year.
+/// RFC 2822 dates may have any zone; the result is always converted to UTC.
+pub(crate) fn parse(value: &str) -> Result<OffsetDateTime> {
+    parse_relative(value, SystemClock.now().year())
+}
+
+/// Parse an HTTP date, resolving two-digit years relative to `current_year`
+fn parse_relative(value: &str, current_year: i32) -> Result<OffsetDateTime> {
+    let value = value.trim();
+    if let Ok(date) = OffsetDateTime::parse(value, &Rfc2822) {
+        return Ok(date.to_offset(UtcOffset::UTC));
+    }
+    parse_rfc850(value, current_year)
+        .or_else(|| PrimitiveDateTime::parse(value, ASCTIME).ok())
+        .map(PrimitiveDateTime::assume_utc)
+        .ok_or_else(|| Error::InvalidHttpDate(value.to_string()))
+}
+
+fn parse_rfc850(value: &str, current_year: i32) -> Option<PrimitiveDateTime> {
+    let mut parsed = Parsed::new();
+    let rest = parsed.parse_items(value.as_bytes(), RFC850).ok()?;
+    if !rest.is_empty() {
+        return None;
+    }
+    parsed.set_year(expand_year(parsed.year_last_two()?, current_year))?;
+    PrimitiveDateTime::try_from(parsed).ok()
+}
+
+/// Expand a two-digit year.
+///
+/// RFC 9110 requires years which appear to be more than 50 years in the
+/// future to be interpreted as the most recent past year with the same
+/// last two digits.
+fn expand_year(last_two: u8, current_year: i32) -> i32 {
+    let year = current_year - current_year.rem_euclid(100) + i32::from(last_two);
+    if year > current_year + 50 {
+        year - 100
+    } else if year + 100 <= current_year + 50 {
+        year + 100
+    } else {
+        year
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn parse_http_date_formats() {
+        for value in [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+        ] {
+            assert_eq!(
+                parse_relative(value, 2023).unwrap(),
+                datetime!(1994-11-06 08:49:37 UTC),
+                "{value}"
+            );
+        }
+        assert_eq!(
+            parse_relative("Wed Mar 15 10:00:00 2023", 2023).unwrap(),
+            datetime!(2023-03-15 10:00:00 UTC)
+        );
+    }
+
+    #[test]
+    fn convert_zones_to_utc() {
+        for (value, expected) in [
+            (
+                "Wed, 01 Mar 2023 10:00:00 GMT",
+                datetime!(2023-03-01 10:00:00 UTC),
+            ),
+            (
+                "Wed, 01 Mar 2023 10:00:00 UT",
+                datetime!(2023-03-01 10:00:00 UTC),
+            ),
+            (
+                "Wed, 01 Mar 2023 10:00:00 +0100",
+                datetime!(2023-03-01 09:00:00 UTC),
+            ),
+            (
+                "Wed, 01 Mar 2023 10:00:00 -0530",
+                datetime!(2023-03-01 15:30:00 UTC),
+            ),
+            (
+                "Wed, 01 Mar 2023 10:00:00 EST",
+                datetime!(2023-03-01 15:00:00 UTC),
+            ),
+            (
+                "Wed, 01 Mar 2023 10:00:00 PDT",
+                datetime!(2023-03-01 17:00:00 UTC),
+            ),
+        ] {
+            let date = parse_relative(value, 2023).unwrap();
+            assert_eq!(date, expected, "{value}");
+            assert_eq!(date.offset(), UtcOffset::UTC, "{value}");
+        }
+    }
+
+    #[test]
+    fn expand_two_digit_years() {
+        assert_eq!(
+            parse_relative("Wednesday, 01-Mar-23 10:00:00 GMT", 2023).unwrap(),
+            datetime!(2023-03-01 10:00:00 UTC)
+        );
+        assert_eq!(expand_year(73, 2023), 2073);
+        assert_eq!(expand_year(74, 2023), 1974);
+        assert_eq!(expand_year(5, 2095), 2105);
+        assert_eq!(expand_year(94, 2095), 2094);
+    }
+
+    #[test]
+    fn reject_invalid_http_dates() {
+        for value in [
+            "",
+            "soon",
+            "Sunday, 06-Nov-94 08:49:37 GMT trailing",
+            "Sunday, 06-Nov-1994 08:49:37 GMT",
+            "Sun Nov  6 08:49:37",
+            "Sun Nov 32 08:49:37 1994",
+        ] {
+            assert!(parse_relative(value, 2023).is_err(), "{value}");
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use headers::HeaderValue;
+use time::Duration;
+
+use crate::casesensitive_headermap::{CaseSensitiveHeaderMap, TryIntoHeaderMap};
+use crate::error::{Error, Result};
+use crate::reset_time::ResetTime;
+use crate::structured::{self, ListEntry};
+
+use super::{bytes_param, count_param, member_name, Policy, RateLimitPolicy};
+
+/// A single named limit as sent in the `RateLimit` header
+///
+/// For example, `"default";r=50;t=30` means that 50 units of the quota of
+/// the `default` policy are left, and that the quota resets in 30 seconds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Limit {
+    /// Name of the policy this limit belongs to
+    pub name: String,
+    /// Remaining quota units (`r`)
+    pub remaining: usize,
+    /// The time at which the quota will be reset (`t`)
+    pub reset: ResetTime,
+    /// Partition key of the limit (`pk`)
+    pub partition_key: Option<Vec<u8>>,
+    /// The policy of the same name from the `RateLimit-Policy` header, if any
+    pub policy: Option<Policy>,
+    /// Estimated offset of the server clock from the local clock
+    pub(crate) skew: Option<Duration>,
+}
+
+impl Limit {
+    /// Create a limit from a member of the `RateLimit` list
+    fn from_entry(entry: &ListEntry) -> Result<Self> {
+        let invalid = Error::InvalidRateLimit;
+        let (name, item) = member_name(entry).ok_or_else(|| invalid(format!("{entry:?}")))?;
+        let remaining = count_param(name, item, "r", invalid)?.ok_or(Error::MissingRemaining)?;
+        let reset = count_param(name, item, "t", invalid)?.ok_or(Error::MissingReset)?;
+
+        Ok(Self {
+            name: name.to_string(),
+            remaining,
+            reset: ResetTime::Seconds(reset),
+            partition_key: bytes_param(name, item, "pk", invalid)?,
+            policy: None,
+            skew: None,
+        })
+    }
+
+    /// Get the quota of the associated policy
+    #[must_use]
+    pub const fn limit(&self) -> Option<usize> {
+        match &self.policy {
+            Some(policy) => Some(policy.quota),
+            None => None,
+        }
+    }
+
+    /// Get the remaining quota units
+    #[must_use]
+    pub const fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Get the remaining quota units as a share of the quota
+    /// of the associated policy
+    fn remaining_share(&self) -> Option<f64> {
+        let quota = self.limit()?;
+        if quota == 0 {
+            return Some(0.0);
+        }
+        Some(self.remaining as f64 / quota as f64)
+    }
+
+    /// Get the time at which the quota will be reset
+    #[must_use]
+    pub const fn reset(&self) -> ResetTime {
+        self.reset
+    }
+
+    /// Get the estimated offset of the server clock from the local clock,
+    /// see [`Anchor::skew`](crate::Anchor::skew)
+    #[must_use]
+    pub const fn skew(&self) -> Option<Duration> {
+        self.skew
+    }
+}
+
+/// HTTP rate limits as parsed from the structured `RateLimit` header
+///
+/// Newer revisions of the draft replace the `RateLimit-Limit`,
+/// `RateLimit-Remaining` and `RateLimit-Reset` headers with a single list,
+/// which can hold multiple named limits:
+///
+/// ```text
+/// RateLimit-Policy: "burst";q=100;w=60,"daily";q=1000;w=86400
+/// RateLimit: "burst";r=50;t=30,"daily";r=900;t=3600
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// All limits in the order they were sent
+    pub limits: Vec<Limit>,
+}
+
+impl RateLimit {
+    /// Extracts the named limits from the `RateLimit` HTTP header.
+    ///
+    /// Each limit is linked to the policy of the same name
+    /// from the `RateLimit-Policy` header if it is present.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the header is missing
+    /// or if its value cannot be parsed.
+    pub fn new<T: TryIntoHeaderMap>(headers: T) -> std::result::Result<Self, Error> {
+        let headers = headers.try_into_header_map()?;
+        let value = Self::get_rate_limit_header(&headers).ok_or(Error::MissingRateLimit)?;
+        let mut limits = structured::parse_list(value.to_str()?)?
+            .iter()
+            .map(Limit::from_entry)
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Ok(policy) = RateLimitPolicy::from_header_map(&headers) {
+            for limit in &mut limits {
+                limit.policy = policy.get(&limit.name).cloned();
+            }
+        }
+        Ok(Self { limits })
+    }
+
+    /// Get the `RateLimit` header value.
+    ///
+    /// Repeated headers are combined into a single list.
+    fn get_rate_limit_header(header_map: &CaseSensitiveHeaderMap) -> Option<HeaderValue> {
+        header_map
+            .get_list("RateLimit")
+            .or_else(|| header_map.get_list("ratelimit"))
+    }
+
+    /// Get all limits
+    #[must_use]
+    pub fn limits(&self) -> &[Limit] {
+        &self.limits
+    }
+
+    /// Get a limit by the name of its policy
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Limit> {
+        self.limits.iter().find(|limit| limit.name == name)
+    }
+
+    /// Get the limit with the smallest share of its quota remaining.
+    ///
+    /// Remaining units of policies with different quota units, e.g.
+    /// requests and content bytes, cannot be compared directly, so the
+    /// share of the quota decides. Exhausted limits always come first.
+    /// Limits without a policy have an unknown quota, so they rank after
+    /// the others and are compared by their remaining units among each other.
+    #[must_use]
+    pub fn most_restrictive(&self) -> Option<&Limit> {
+        self.limits.iter().min_by(|a, b| {
+            (a.remaining > 0).cmp(&(b.remaining > 0)).then_with(|| {
+                match (a.remaining_share(), b.remaining_share()) {
+                    (Some(a), Some(b)) => a.total_cmp(&b),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => a.remaining.cmp(&b.remaining),
+                }
+            })
+        })
+    }
+}
+
+impl FromStr for RateLimit {
+    type Err = Error;
+
+    fn from_str(map: &str) -> Result<Self> {
+        RateLimit::new(CaseSensitiveHeaderMap::from_str(map)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use time::Duration;
+
+    #[test]
+    fn parse_single_limit() {
+        let rate = RateLimit::from_str(r#"RateLimit: "default";r=50;t=30"#).unwrap();
+        assert_eq!(
+            rate.limits(),
+            &[Limit {
+                name: "default".to_string(),
+                remaining: 50,
+                reset: ResetTime::Seconds(30),
+                partition_key: None,
+                policy: None,
+                skew: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_limits_with_policies() {
+        let headers = indoc! {r#"
+            RateLimit-Policy: "burst";q=100;w=60,"daily";q=1000;w=86400
+            RateLimit: "burst";r=50;t=30,"daily";r=900;t=3600
+        "#};
+
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(rate.limits().len(), 2);
+
+        let burst = rate.get("burst").unwrap();
+        assert_eq!(burst.limit(), Some(100));
+        assert_eq!(
+            burst.policy.as_ref().unwrap().window,
+            Some(Duration::seconds(60))
+        );
+
+        let daily = rate.get("daily").unwrap();
+        assert_eq!(daily.remaining(), 900);
+        assert_eq!(daily.reset(), ResetTime::Seconds(3600));
+
+        assert_eq!(rate.most_restrictive().unwrap().name, "burst");
+    }
+
+    #[test]
+    fn compare_limits_by_share_of_quota() {
+        // 10 of 20 requests are a larger share than 500 of 100000 bytes
+        let headers = indoc! {r#"
+            RateLimit-Policy: "requests";q=20;w=60,"upload";q=100000;qu="content-bytes";w=60
+            RateLimit: "requests";r=10;t=30,"upload";r=500;t=30
+        "#};
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(rate.most_restrictive().unwrap().name, "upload");
+
+        // An exhausted limit is the most restrictive, even without a policy
+        let headers = indoc! {r#"
+            RateLimit-Policy: "requests";q=20;w=60
+            RateLimit: "requests";r=1;t=30,"unknown";r=0;t=30
+        "#};
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(rate.most_restrictive().unwrap().name, "unknown");
+
+        // Without policies, all limits count requests
+        let rate = RateLimit::from_str(r#"RateLimit: "a";r=10;t=30,"b";r=5;t=30"#).unwrap();
+        assert_eq!(rate.most_restrictive().unwrap().name, "b");
+    }
+
+    #[test]
+    fn parse_repeated_headers() {
+        let headers = indoc! {r#"
+            RateLimit-Policy: "burst";q=100;w=60
+            RateLimit-Policy: "daily";q=1000;w=86400
+            RateLimit: "burst";r=50;t=30
+            RateLimit: "daily";r=900;t=3600
+        "#};
+
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(rate.limits().len(), 2);
+        assert_eq!(rate.get("daily").unwrap().limit(), Some(1000));
+    }
+
+    #[test]
+    fn parse_limit_without_remaining() {
+        assert!(matches!(
+            RateLimit::from_str(r#"RateLimit: "default";t=30"#),
+            Err(Error::MissingRemaining)
+        ));
+    }
+
+    #[test]
+    fn parse_limit_with_partition_key() {
+        let rate =
+            RateLimit::from_str(r#"RateLimit: "default";r=5;t=1;pk=:dXNlcjEyMw==:"#).unwrap();
+        assert_eq!(rate.limits()[0].partition_key, Some(b"user123".to_vec()));
+        assert!(RateLimit::from_str(r#"RateLimit: "default";r=5;t=1;pk="user123""#).is_err());
+    }
+
+    #[test]
+    fn missing_rate_limit() {
+        assert!(matches!(
+            RateLimit::from_str("RateLimit-Remaining: 10"),
+            Err(Error::MissingRateLimit)
+        ));
+    }
+}
@@ -0,0 +1,61 @@
+//! Rate limit headers as defined in [draft-ietf-httpapi-ratelimit-headers][draft].
+//!
+//! This draft supersedes the "polli" draft and introduces the
+//! `RateLimit-Policy` header, which declares the quota policies of a server.
+//! Later revisions also replace the `RateLimit-Limit`, `RateLimit-Remaining`
+//! and `RateLimit-Reset` headers with a single `RateLimit` header.
+//!
+//! [draft]: https://datatracker.ietf.org/doc/draft-ietf-httpapi-ratelimit-headers/
+mod limit;
+mod policy;
+
+pub use limit::{Limit, RateLimit};
+pub use policy::{Policy, QuotaUnit, RateLimitPolicy};
+
+use crate::error::{Error, Result};
+use crate::structured::{Item, ListEntry};
+
+/// Get the name of a list member, which is an item holding a string or token
+fn member_name(entry: &ListEntry) -> Option<(&str, &Item)> {
+    let item = entry.as_item()?;
+    Some((item.bare_item.as_str()?, item))
+}
+
+/// Get a parameter of a member which holds a non-negative integer.
+///
+/// Returns `Ok(None)` if the parameter is missing and reports
+/// other types with the given `invalid` error variant.
+fn count_param(
+    name: &str,
+    item: &Item,
+    key: &str,
+    invalid: fn(String) -> Error,
+) -> Result<Option<usize>> {
+    item.params
+        .get(key)
+        .map(|value| {
+            value
+                .as_integer()
+                .and_then(|i| usize::try_from(i).ok())
+                .ok_or_else(|| invalid(format!("{name}: {key}")))
+        })
+        .transpose()
+}
+
+/// Get a parameter of a member which holds a byte sequence
+fn bytes_param(
+    name: &str,
+    item: &Item,
+    key: &str,
+    invalid: fn(String) -> Error,
+) -> Result<Option<Vec<u8>>> {
+    item.params
+        .get(key)
+        .map(|value| {
+            value
+                .as_bytes()
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| invalid(format!("{name}: {key}")))
+        })
+        .transpose()
+}
@@ -0,0 +1,227 @@
+use std::str::FromStr;
+
+use headers::HeaderValue;
+use time::Duration;
+
+use crate::casesensitive_headermap::{CaseSensitiveHeaderMap, TryIntoHeaderMap};
+use crate::error::{Error, Result};
+use crate::structured::{self, ListEntry};
+
+use super::{bytes_param, count_param, member_name};
+
+/// The unit in which a quota is expressed
+///
+/// See <https://datatracker.ietf.org/doc/html/draft-ietf-httpapi-ratelimit-headers#name-quota-unit-parameter>
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum QuotaUnit {
+    /// Number of requests (the default)
+    #[default]
+    Requests,
+    /// Number of bytes in the content of requests
+    ContentBytes,
+    /// Number of requests served at the same time
+    ConcurrentRequests,
+    /// A unit not defined by the draft
+    Other(String),
+}
+
+impl From<&str> for QuotaUnit {
+    fn from(unit: &str) -> Self {
+        match unit {
+            "requests" => Self::Requests,
+            "content-bytes" => Self::ContentBytes,
+            "concurrent-requests" => Self::ConcurrentRequests,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+/// A single quota policy as declared in the `RateLimit-Policy` header
+///
+/// For example, `"daily";q=1000;w=86400` declares a policy named `daily`,
+/// which allows 1000 requests per day.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Policy {
+    /// Name of the policy
+    pub name: String,
+    /// Quota allocated by the policy (`q`)
+    pub quota: usize,
+    /// Unit of the quota (`qu`)
+    pub quota_unit: QuotaUnit,
+    /// Time window of the policy (`w`).
+    /// It is optional, because servers are not required to declare it.
+    pub window: Option<Duration>,
+    /// Partition key of the policy (`pk`), e.g. an API key or a client IP
+    pub partition_key: Option<Vec<u8>>,
+}
+
+impl Policy {
+    /// Create a policy from a member of the `RateLimit-Policy` list
+    fn from_entry(entry: &ListEntry) -> Result<Self> {
+        let invalid = Error::InvalidPolicy;
+        let (name, item) = member_name(entry).ok_or_else(|| invalid(format!("{entry:?}")))?;
+        let quota = count_param(name, item, "q", invalid)?
+            .ok_or_else(|| invalid(format!("{name}: missing q")))?;
+        let quota_unit = match item.params.get("qu") {
+            Some(unit) => unit
+                .as_str()
+                .map(QuotaUnit::from)
+                .ok_or_else(|| invalid(format!("{name}: qu")))?,
+            None => QuotaUnit::default(),
+        };
+        let window = count_param(name, item, "w", invalid)?
+            .map(|w| i64::try_from(w).map(Duration::seconds))
+            .transpose()
+            .map_err(|_| invalid(format!("{name}: w")))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            quota,
+            quota_unit,
+            window,
+            partition_key: bytes_param(name, item, "pk", invalid)?,
+        })
+    }
+}
+
+/// Quota policies as parsed from the `RateLimit-Policy` header
+///
+/// A server can declare multiple policies at once,
+/// e.g. a burst limit and a daily limit:
+///
+/// ```text
+/// RateLimit-Policy: "burst";q=100;w=60,"daily";q=1000;w=86400
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateLimitPolicy {
+    /// All policies in the order they were declared
+    pub policies: Vec<Policy>,
+}
+
+impl RateLimitPolicy {
+    /// Extracts the quota policies from the `RateLimit-Policy` HTTP header
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the header is missing
+    /// or if its value cannot be parsed.
+    pub fn new<T: TryIntoHeaderMap>(headers: T) -> std::result::Result<Self, Error> {
+        Self::from_header_map(&headers.try_into_header_map()?)
+    }
+
+    pub(crate) fn from_header_map(headers: &CaseSensitiveHeaderMap) -> Result<Self> {
+        let value = Self::get_policy_header(headers).ok_or(Error::MissingPolicy)?;
+        let policies = structured::parse_list(value.to_str()?)?
+            .iter()
+            .map(Policy::from_entry)
+            .collect::<Result<_>>()?;
+        Ok(Self { policies })
+    }
+
+    /// Get the `RateLimit-Policy` header value.
+    ///
+    /// Repeated headers are combined into a single list.
+    fn get_policy_header(header_map: &CaseSensitiveHeaderMap) -> Option<HeaderValue> {
+        header_map
+            .get_list("RateLimit-Policy")
+            .or_else(|| header_map.get_list("ratelimit-policy"))
+    }
+
+    /// Get all declared policies
+    #[must_use]
+    pub fn policies(&self) -> &[Policy] {
+        &self.policies
+    }
+
+    /// Get a policy by its name
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Policy> {
+        self.policies.iter().find(|policy| policy.name == name)
+    }
+
+    /// Find the policy which applies to the given limit
+    ///
+    /// If there is only one policy, it applies to every limit.
+    /// Otherwise the policy with a matching quota is returned.
+    #[must_use]
+    pub fn for_limit(&self, limit: usize) -> Option<&Policy> {
+        match self.policies.as_slice() {
+            [policy] => Some(policy),
+            policies => policies.iter().find(|policy| policy.quota == limit),
+        }
+    }
+}
+
+impl FromStr for RateLimitPolicy {
+    type Err = Error;
+
+    fn from_str(map: &str) -> Result<Self> {
+        RateLimitPolicy::new(CaseSensitiveHeaderMap::from_str(map)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_single_policy() {
+        let policy =
+            RateLimitPolicy::from_str(r#"RateLimit-Policy: "default";q=100;w=10"#).unwrap();
+        assert_eq!(
+            policy.policies(),
+            &[Policy {
+                name: "default".to_string(),
+                quota: 100,
+                quota_unit: QuotaUnit::Requests,
+                window: Some(Duration::seconds(10)),
+                partition_key: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_multiple_policies() {
+        let policy = RateLimitPolicy::from_str(
+            r#"RateLimit-Policy: "burst";q=100;w=60,"daily";q=1000;w=86400;qu="content-bytes";pk=:dXNlcjEyMw==:"#,
+        )
+        .unwrap();
+        assert_eq!(policy.policies().len(), 2);
+
+        let daily = policy.get("daily").unwrap();
+        assert_eq!(daily.quota, 1000);
+        assert_eq!(daily.window, Some(Duration::DAY));
+        assert_eq!(daily.quota_unit, QuotaUnit::ContentBytes);
+        assert_eq!(daily.partition_key, Some(b"user123".to_vec()));
+
+        assert_eq!(policy.for_limit(100).unwrap().name, "burst");
+        assert!(policy.for_limit(42).is_none());
+    }
+
+    #[test]
+    fn parse_policy_without_window() {
+        let policy = RateLimitPolicy::from_str("ratelimit-policy: default;q=50").unwrap();
+        assert_eq!(policy.policies()[0].window, None);
+        assert_eq!(policy.for_limit(42).unwrap().quota, 50);
+    }
+
+    #[test]
+    fn parse_invalid_policies() {
+        assert!(RateLimitPolicy::from_str("RateLimit-Policy: default;w=60").is_err());
+        assert!(RateLimitPolicy::from_str("RateLimit-Policy: default;q=-1").is_err());
+        assert!(RateLimitPolicy::from_str("RateLimit-Policy: default;q=\"10\"").is_err());
+        assert!(RateLimitPolicy::from_str("RateLimit-Policy: (a b);q=10").is_err());
+        assert!(matches!(
+            RateLimitPolicy::from_str("RateLimit-Policy: default;q=10,"),
+            Err(Error::UnexpectedEnd)
+        ));
+    }
+
+    #[test]
+    fn missing_policy() {
+        assert!(matches!(
+            RateLimitPolicy::from_str("RateLimit-Limit: 100"),
+            Err(Error::MissingPolicy)
+        ));
+    }
+}
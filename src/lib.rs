@@ -11,25 +11,38 @@
     variant_size_differences,
     clippy::missing_const_for_fn
 )]
-#![deny(anonymous_parameters, macro_use_extern_crate, pointer_structural_match)]
+#![deny(anonymous_parameters, macro_use_extern_crate)]
 #![deny(missing_docs)]
 #![allow(clippy::module_name_repetitions)]
 
 mod casesensitive_headermap;
 mod convert;
+mod duration;
 mod error;
+mod http_date;
+mod limits;
 mod reset_time;
 
+pub mod clock;
 pub mod headers;
+pub mod ietf;
+pub mod response;
 pub mod retryafter;
+pub mod structured;
 
+use std::cmp::Ordering;
 use std::str::FromStr;
 
-use casesensitive_headermap::CaseSensitiveHeaderMap;
-use error::{Error, Result};
+use clock::{Clock, ServerClock, SystemClock};
+use error::Result;
+use headers::{VariantRegistry, RATE_LIMIT_HEADERS};
+use time::{Duration, OffsetDateTime};
 
+pub use casesensitive_headermap::{CaseSensitiveHeaderMap, TryIntoHeaderMap};
+pub use error::{Error, Warning};
 pub use headers::{Headers, Vendor};
-pub use reset_time::ResetTime;
+pub use limits::RateLimits;
+pub use reset_time::{Anchor, ResetTime, ResetTimeKind, Wait};
 
 /// Rate Limit information, parsed from HTTP headers.
 ///
@@ -37,46 +50,261 @@ pub use reset_time::ResetTime;
 /// The following variants are supported:
 ///
 /// - [IETF "Polly" draft][ietf]
+/// - [IETF `RateLimit` header][draft_new] as defined in later drafts
 /// - [Retry-After][retryafter]
 ///
 /// [ietf]: https://datatracker.ietf.org/doc/html/draft-polli-ratelimit-headers-00
+/// [draft_new]: https://datatracker.ietf.org/doc/draft-ietf-httpapi-ratelimit-headers/
 /// [retryafter]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Retry-After
 ///
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RateLimit {
     /// Rate limit information as per the [IETF "Polly" draft][ietf].
     Rfc6585(headers::Headers),
+    /// Rate limit information as per the structured `RateLimit` header
+    /// of the [IETF draft][draft_new].
+    ///
+    /// If the header contains multiple limits, this is the most restrictive one.
+    Ietf(ietf::Limit),
     /// Rate limit information as per the [Retry-After][retryafter] header.
     RetryAfter(retryafter::RateLimit),
 }
 
 impl RateLimit {
     /// Create a new `RateLimit` from a `http::HeaderMap`.
-    pub fn new<T: Into<CaseSensitiveHeaderMap>>(headers: T) -> std::result::Result<Self, Error> {
-        let headers = headers.into();
-        let rfc6585 = headers::Headers::new(headers.clone());
-        let retryafter = retryafter::RateLimit::new(headers);
-
-        match (rfc6585, retryafter) {
-            (Ok(rfc6585), Ok(retryafter)) => {
-                if rfc6585.reset > retryafter.reset {
-                    Ok(Self::Rfc6585(rfc6585))
-                } else {
-                    Ok(Self::RetryAfter(retryafter))
+    ///
+    /// If the response contains multiple rate limits, only one of them
+    /// is returned. Use [`RateLimits`] to get all of them.
+    pub fn new<T: TryIntoHeaderMap>(headers: T) -> std::result::Result<Self, Error> {
+        Self::with_clock(headers, &SystemClock)
+    }
+
+    /// Create a new `RateLimit` from a `http::HeaderMap`,
+    /// choosing between multiple rate limits based on the current time
+    /// of the given clock.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if no rate limit could be found
+    /// in the given headers.
+    pub fn with_clock<T: TryIntoHeaderMap, C: Clock + ?Sized>(
+        headers: T,
+        clock: &C,
+    ) -> std::result::Result<Self, Error> {
+        Self::select(
+            headers.try_into_header_map()?,
+            clock.now(),
+            &RATE_LIMIT_HEADERS,
+        )
+    }
+
+    /// Create a new `RateLimit` from a `http::HeaderMap`,
+    /// consulting the vendor variants of the given registry,
+    /// see [`Headers::with_registry`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if no rate limit could be found
+    /// in the given headers.
+    pub fn with_registry<T: TryIntoHeaderMap>(
+        headers: T,
+        registry: &VariantRegistry,
+    ) -> std::result::Result<Self, Error> {
+        Self::select(headers.try_into_header_map()?, SystemClock.now(), registry)
+    }
+
+    /// Choose the rate limit which resets last,
+    /// resolving relative reset times against `now`.
+    /// A quota without a reset time loses against `Retry-After`.
+    fn select(
+        headers: CaseSensitiveHeaderMap,
+        now: OffsetDateTime,
+        registry: &VariantRegistry,
+    ) -> Result<Self> {
+        let retryafter = retryafter::RateLimit::new(headers.clone());
+        let rate_limit = Self::quota(headers, registry, now);
+
+        match (rate_limit, retryafter) {
+            (Ok(rate_limit), Ok(retryafter)) => match rate_limit.reset() {
+                Some(reset) if reset.cmp_at(&retryafter.reset, now) == Ordering::Greater => {
+                    Ok(rate_limit)
                 }
-            }
-            (Ok(rfc6585), Err(_)) => Ok(Self::Rfc6585(rfc6585)),
+                _ => Ok(Self::RetryAfter(retryafter)),
+            },
+            (Ok(rate_limit), Err(_)) => Ok(rate_limit),
             (Err(_), Ok(retryafter)) => Ok(Self::RetryAfter(retryafter)),
             (Err(e), Err(_)) => Err(e),
         }
     }
 
+    /// Get the quota from the `RateLimit` header or,
+    /// if it is missing, from vendor specific headers.
+    ///
+    /// An invalid `RateLimit` header is an error, so the vendor headers
+    /// are not consulted.
+    pub(crate) fn quota(
+        headers: CaseSensitiveHeaderMap,
+        registry: &VariantRegistry,
+        now: OffsetDateTime,
+    ) -> Result<Self> {
+        match ietf::RateLimit::new(headers.clone()) {
+            Ok(ietf) => ietf
+                .most_restrictive()
+                .cloned()
+                .map(Self::Ietf)
+                .ok_or(Error::MissingRateLimit),
+            Err(Error::MissingRateLimit) => {
+                headers::Headers::parse_at(&headers, registry, now).map(Self::Rfc6585)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Create a new `RateLimit` and anchor a relative reset time
+    /// to the given reference instant.
+    ///
+    /// The reset time of the result is a datetime whenever the reference
+    /// instant is known, so it stays correct when the response is processed
+    /// later.
+    ///
+    /// Reset times are kept on the server clock. If the response contains
+    /// a `Date` header, the clock skew between server and client is
+    /// estimated (see [`Anchor::skew`]) and applied by
+    /// [`RateLimit::duration`], [`RateLimit::seconds`] and [`RateLimit::wait`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if no rate limit could be found
+    /// in the given headers.
+    pub fn with_anchor<T: TryIntoHeaderMap>(
+        headers: T,
+        anchor: Anchor,
+    ) -> std::result::Result<Self, Error> {
+        Self::with_anchor_at(headers, anchor, &SystemClock)
+    }
+
+    /// Create a new `RateLimit` and anchor a relative reset time
+    /// to the given reference instant, see [`RateLimit::with_anchor`].
+    ///
+    /// The current time of the given clock is used to estimate the clock skew
+    /// for [`Anchor::Date`] and to choose between multiple rate limits
+    /// if the reference instant is unknown, e.g. when anchoring to a missing
+    /// `Date` header.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if no rate limit could be found
+    /// in the given headers.
+    pub fn with_anchor_at<T: TryIntoHeaderMap, C: Clock + ?Sized>(
+        headers: T,
+        anchor: Anchor,
+        clock: &C,
+    ) -> std::result::Result<Self, Error> {
+        let headers = headers.try_into_header_map()?;
+        let now = clock.now();
+        let reference = anchor.instant(&headers);
+        let skew = anchor.estimate_skew(&headers, now);
+        let mut rate_limit = Self::select(headers, reference.unwrap_or(now), &RATE_LIMIT_HEADERS)?;
+        rate_limit.anchor(reference, skew);
+        Ok(rate_limit)
+    }
+
+    /// Anchor a relative reset time to the given reference instant
+    /// and remember the clock skew
+    pub(crate) fn anchor(&mut self, reference: Option<OffsetDateTime>, skew: Option<Duration>) {
+        let (reset, skew_field) = match self {
+            Self::Rfc6585(rfc6585) => (rfc6585.reset.as_mut(), &mut rfc6585.skew),
+            Self::Ietf(ietf) => (Some(&mut ietf.reset), &mut ietf.skew),
+            Self::RetryAfter(retryafter) => (Some(&mut retryafter.reset), &mut retryafter.skew),
+        };
+        if let (Some(reset), Some(reference)) = (reset, reference) {
+            *reset = reset.anchor(reference);
+        }
+        *skew_field = skew;
+    }
+
+    /// Get the estimated offset of the server clock from the local clock.
+    ///
+    /// This is only known when parsing with [`RateLimit::with_anchor`] and
+    /// the response contains a `Date` header. A positive skew means that
+    /// the server clock is ahead.
+    pub const fn skew(&self) -> Option<Duration> {
+        match self {
+            Self::Rfc6585(rfc6585) => rfc6585.skew,
+            Self::Ietf(ietf) => ietf.skew,
+            Self::RetryAfter(retryafter) => retryafter.skew,
+        }
+    }
+
+    /// Get the number of seconds until the rate limit gets lifted,
+    /// correcting for the clock skew, see [`ResetTime::seconds`].
+    ///
+    /// Returns `None` if the reset time is unknown.
+    #[must_use]
+    pub fn seconds(&self) -> Option<usize> {
+        self.seconds_at(&SystemClock)
+    }
+
+    /// Get the number of seconds until the rate limit gets lifted,
+    /// based on the current time of the given clock and corrected for
+    /// the clock skew.
+    #[must_use]
+    pub fn seconds_at<C: Clock + ?Sized>(&self, clock: &C) -> Option<usize> {
+        let clock = self.server_clock(clock);
+        self.reset().map(|reset| reset.seconds_at(&clock))
+    }
+
+    /// Get the duration until the rate limit gets lifted,
+    /// correcting for the clock skew, see [`ResetTime::duration`].
+    ///
+    /// Returns `None` if the reset time is unknown.
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration_at(&SystemClock)
+    }
+
+    /// Get the duration until the rate limit gets lifted,
+    /// based on the current time of the given clock and corrected for
+    /// the clock skew.
+    #[must_use]
+    pub fn duration_at<C: Clock + ?Sized>(&self, clock: &C) -> Option<Duration> {
+        let clock = self.server_clock(clock);
+        self.reset().map(|reset| reset.duration_at(&clock))
+    }
+
+    /// Get the time to wait until the rate limit gets lifted,
+    /// correcting for the clock skew, see [`ResetTime::wait`].
+    ///
+    /// Returns `None` if the reset time is unknown.
+    #[must_use]
+    pub fn wait(&self) -> Option<Wait> {
+        self.wait_at(&SystemClock)
+    }
+
+    /// Get the time to wait until the rate limit gets lifted,
+    /// based on the current time of the given clock and corrected for
+    /// the clock skew.
+    #[must_use]
+    pub fn wait_at<C: Clock + ?Sized>(&self, clock: &C) -> Option<Wait> {
+        let clock = self.server_clock(clock);
+        self.reset().map(|reset| reset.wait_at(&clock))
+    }
+
+    /// Get the server clock, which is offset from the given local clock
+    /// by the estimated skew
+    fn server_clock<'a, C: Clock + ?Sized>(&self, clock: &'a C) -> ServerClock<'a, C> {
+        ServerClock::new(clock, self.skew().unwrap_or(Duration::ZERO))
+    }
+
     /// Get `reset` time.
     /// This is the time when the rate limit will be reset.
-    pub const fn reset(&self) -> ResetTime {
+    ///
+    /// Returns `None` if the vendor does not send a reset time.
+    pub const fn reset(&self) -> Option<ResetTime> {
         match self {
             Self::Rfc6585(rfc6585) => rfc6585.reset,
-            Self::RetryAfter(retryafter) => retryafter.reset,
+            Self::Ietf(ietf) => Some(ietf.reset),
+            Self::RetryAfter(retryafter) => Some(retryafter.reset),
         }
     }
 
@@ -86,16 +314,28 @@ impl RateLimit {
     pub const fn limit(&self) -> Option<usize> {
         match self {
             Self::Rfc6585(rfc6585) => Some(rfc6585.limit),
+            Self::Ietf(ietf) => ietf.limit(),
             Self::RetryAfter(_) => None,
         }
     }
 
+    /// Get the problems found while parsing, e.g. conflicting values
+    /// of a repeated header.
+    pub fn warnings(&self) -> &[Warning] {
+        match self {
+            Self::Rfc6585(rfc6585) => &rfc6585.warnings,
+            Self::Ietf(_) => &[],
+            Self::RetryAfter(retryafter) => &retryafter.warnings,
+        }
+    }
+
     /// Get `remaining` value.
     ///
     /// This is the number of requests remaining in the current time window.
     pub const fn remaining(&self) -> Option<usize> {
         match self {
             Self::Rfc6585(rfc6585) => Some(rfc6585.remaining),
+            Self::Ietf(ietf) => Some(ietf.remaining),
             Self::RetryAfter(_) => None,
         }
     }
@@ -114,12 +354,30 @@ mod tests {
     use super::*;
     use indoc::indoc;
     use std::str::FromStr;
+    use std::time::Duration as StdDuration;
     use time::macros::datetime;
 
+    use crate::clock::ManualClock;
     use crate::reset_time::ResetTime;
 
     #[test]
     fn use_later_reset_time_date() {
+        let headers = indoc! {"
+            X-Ratelimit-Used: 100
+            X-Ratelimit-Remaining: 22
+            X-Ratelimit-Reset: 30
+            Retry-After: Wed, 21 Oct 2099 07:28:00 GMT
+        "};
+
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(
+            rate.reset(),
+            Some(ResetTime::DateTime(datetime!(2099-10-21 7:28:00.0 UTC)))
+        );
+    }
+
+    #[test]
+    fn use_later_reset_time_past_date() {
         let headers = indoc! {"
             X-Ratelimit-Used: 100
             X-Ratelimit-Remaining: 22
@@ -128,9 +386,27 @@ mod tests {
         "};
 
         let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(rate.reset(), Some(ResetTime::Seconds(30)));
+    }
+
+    #[test]
+    fn use_later_reset_time_at_clock() {
+        let headers = indoc! {"
+            X-Ratelimit-Used: 100
+            X-Ratelimit-Remaining: 22
+            X-Ratelimit-Reset: 30
+            Retry-After: Wed, 01 Mar 2023 10:00:20 GMT
+        "};
+
+        let clock = ManualClock::new(datetime!(2023-03-01 10:00:00 UTC));
+        let rate = RateLimit::with_clock(headers, &clock).unwrap();
+        assert_eq!(rate.reset(), Some(ResetTime::Seconds(30)));
+
+        clock.set(datetime!(2023-03-01 9:59:00 UTC));
+        let rate = RateLimit::with_clock(headers, &clock).unwrap();
         assert_eq!(
             rate.reset(),
-            ResetTime::DateTime(datetime!(2015-10-21 7:28:00.0 UTC))
+            Some(ResetTime::DateTime(datetime!(2023-03-01 10:00:20 UTC)))
         );
     }
 
@@ -144,6 +420,146 @@ mod tests {
         "};
 
         let rate = RateLimit::from_str(headers).unwrap();
-        assert_eq!(rate.reset(), ResetTime::Seconds(30));
+        assert_eq!(rate.reset(), Some(ResetTime::Seconds(30)));
+    }
+
+    #[test]
+    fn use_custom_registry() {
+        let acme = headers::RateLimitVariant::builder(Vendor::Custom("Acme".to_string()))
+            .used_header("Acme-Used")
+            .remaining_header("Acme-Remaining")
+            .reset_header("Acme-Reset")
+            .build()
+            .unwrap();
+        let registry = std::iter::once(acme).collect();
+        let headers = "Acme-Used: 3\nAcme-Remaining: 7\nAcme-Reset: 30";
+
+        assert!(RateLimit::from_str(headers).is_err());
+        let rate = RateLimit::with_registry(headers, &registry).unwrap();
+        assert_eq!(rate.limit(), Some(10));
+        assert_eq!(rate.reset(), Some(ResetTime::Seconds(30)));
+    }
+
+    #[test]
+    fn anchor_to_date_header() {
+        let headers = indoc! {"
+            Date: Wed, 01 Mar 2023 10:00:00 GMT
+            Retry-After: 30
+        "};
+
+        let rate = RateLimit::with_anchor(headers, Anchor::Date).unwrap();
+        assert_eq!(
+            rate.reset(),
+            Some(ResetTime::DateTime(datetime!(2023-03-01 10:00:30 UTC)))
+        );
+
+        // The response was received two seconds after the server sent it
+        let received = datetime!(2023-03-01 10:00:02 UTC);
+        let rate = RateLimit::with_anchor(headers, Anchor::Received(received)).unwrap();
+        assert_eq!(
+            rate.reset(),
+            Some(ResetTime::DateTime(datetime!(2023-03-01 10:00:30 UTC)))
+        );
+        assert_eq!(rate.skew(), Some(Duration::seconds(-2)));
+        let clock = ManualClock::new(received);
+        assert_eq!(
+            rate.wait_at(&clock),
+            Some(Wait::Pending(StdDuration::from_secs(30)))
+        );
+    }
+
+    #[test]
+    fn correct_clock_skew() {
+        let headers = indoc! {"
+            Date: Wed, 01 Mar 2023 10:05:00 GMT
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 0
+            x-ratelimit-reset: 1677665100
+        "};
+
+        // The server clock is five minutes ahead
+        let received = datetime!(2023-03-01 10:00:00 UTC);
+        let rate = RateLimit::with_anchor(headers, Anchor::Received(received)).unwrap();
+        assert_eq!(
+            rate.reset(),
+            Some(ResetTime::DateTime(datetime!(2023-03-01 10:05:00 UTC)))
+        );
+        assert_eq!(rate.skew(), Some(Duration::minutes(5)));
+
+        let clock = ManualClock::new(datetime!(2023-03-01 9:59:00 UTC));
+        assert_eq!(rate.seconds_at(&clock), Some(60));
+        assert_eq!(rate.duration_at(&clock), Some(Duration::minutes(1)));
+        clock.set(received);
+        assert_eq!(rate.wait_at(&clock), Some(Wait::Reset));
+
+        // The skew is estimated from the current time when anchoring to the
+        // `Date` header
+        let rate = RateLimit::with_anchor_at(headers, Anchor::Date, &clock).unwrap();
+        assert_eq!(rate.skew(), Some(Duration::minutes(5)));
+        assert_eq!(rate.seconds_at(&clock), Some(0));
+    }
+
+    #[test]
+    fn anchor_without_date_header() {
+        let rate = RateLimit::with_anchor("Retry-After: 30", Anchor::Date).unwrap();
+        assert_eq!(rate.reset(), Some(ResetTime::Seconds(30)));
+    }
+
+    #[test]
+    fn anchor_without_date_header_at_clock() {
+        let headers = indoc! {"
+            X-Ratelimit-Used: 100
+            X-Ratelimit-Remaining: 22
+            X-Ratelimit-Reset: 30
+            Retry-After: Wed, 01 Mar 2023 10:00:20 GMT
+        "};
+
+        let clock = ManualClock::new(datetime!(2023-03-01 10:00:00 UTC));
+        let rate = RateLimit::with_anchor_at(headers, Anchor::Date, &clock).unwrap();
+        assert_eq!(rate.reset(), Some(ResetTime::Seconds(30)));
+
+        clock.set(datetime!(2023-03-01 9:59:00 UTC));
+        let rate = RateLimit::with_anchor_at(headers, Anchor::Date, &clock).unwrap();
+        assert_eq!(
+            rate.reset(),
+            Some(ResetTime::DateTime(datetime!(2023-03-01 10:00:20 UTC)))
+        );
+    }
+
+    #[test]
+    fn reject_invalid_header_value() {
+        assert!(matches!(
+            RateLimit::new("Retry-After: 30\nX: \u{7f}bad"),
+            Err(Error::InvalidHeaderLine(2, e)) if matches!(*e, Error::InvalidHeaderValue(_))
+        ));
+    }
+
+    #[test]
+    fn use_ietf_rate_limit_header() {
+        let headers = indoc! {r#"
+            RateLimit-Policy: "burst";q=100;w=60,"daily";q=1000;w=86400
+            RateLimit: "burst";r=50;t=30,"daily";r=10;t=3600
+        "#};
+
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert!(matches!(rate, RateLimit::Ietf(ref limit) if limit.name == "daily"));
+        assert_eq!(rate.limit(), Some(1000));
+        assert_eq!(rate.remaining(), Some(10));
+        assert_eq!(rate.reset(), Some(ResetTime::Seconds(3600)));
+    }
+
+    #[test]
+    fn report_invalid_ietf_rate_limit_header() {
+        let headers = indoc! {r#"
+            RateLimit: "default";r=many;t=30
+            X-RateLimit-Limit: 60
+            X-RateLimit-Remaining: 59
+            X-RateLimit-Reset: 30
+        "#};
+
+        assert!(matches!(
+            RateLimit::from_str(headers),
+            Err(Error::InvalidRateLimit(_))
+        ));
     }
 }
@@ -0,0 +1,473 @@
+use std::str::FromStr;
+
+use time::{Duration, OffsetDateTime};
+
+use crate::casesensitive_headermap::{CaseSensitiveHeaderMap, TryIntoHeaderMap};
+use crate::clock::{Clock, ServerClock, SystemClock};
+use crate::error::{Error, Result};
+use crate::headers::{VariantRegistry, RATE_LIMIT_HEADERS};
+use crate::reset_time::{Anchor, ResetTime};
+use crate::{headers, ietf, retryafter, RateLimit};
+
+/// All rate limits found in a single response
+///
+/// Many APIs enforce several independent limits at once, e.g. a burst limit
+/// and a daily limit, or separate limits for requests and tokens.
+/// Clients need to respect all of them, so instead of choosing one limit
+/// like [`RateLimit::new`], this collects every limit in the response.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RateLimits {
+    limits: Vec<RateLimit>,
+    skew: Option<Duration>,
+}
+
+impl RateLimits {
+    /// Extracts all rate limits from the given headers.
+    ///
+    /// This includes every named limit of the IETF `RateLimit` header,
+    /// the limits of every matching vendor variant, e.g. both the request
+    /// and the token limit of OpenAI, and the `Retry-After` header.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if no rate limit could be found
+    /// in the given headers.
+    pub fn new<T: TryIntoHeaderMap>(headers: T) -> std::result::Result<Self, Error> {
+        Self::with_registry(headers, &RATE_LIMIT_HEADERS)
+    }
+
+    /// Extracts all rate limits from the given headers,
+    /// consulting the vendor variants of the given registry.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if no rate limit could be found
+    /// in the given headers.
+    pub fn with_registry<T: TryIntoHeaderMap>(
+        headers: T,
+        registry: &VariantRegistry,
+    ) -> std::result::Result<Self, Error> {
+        Self::collect(headers.try_into_header_map()?, registry, SystemClock.now())
+    }
+
+    /// Collect every rate limit,
+    /// resolving vendor specific reset times against `now`
+    fn collect(
+        headers: CaseSensitiveHeaderMap,
+        registry: &VariantRegistry,
+        now: OffsetDateTime,
+    ) -> Result<Self> {
+        let mut limits = Vec::new();
+
+        match ietf::RateLimit::new(headers.clone()) {
+            Ok(ietf) => limits.extend(ietf.limits.into_iter().map(RateLimit::Ietf)),
+            Err(Error::MissingRateLimit) => {}
+            Err(e) => return Err(e),
+        }
+        let rfc6585 = headers::Headers::all_at(&headers, registry, now);
+        if let Ok(rfc6585) = &rfc6585 {
+            limits.extend(rfc6585.iter().cloned().map(RateLimit::Rfc6585));
+        }
+        if let Ok(retryafter) = retryafter::RateLimit::new(headers) {
+            limits.push(RateLimit::RetryAfter(retryafter));
+        }
+
+        match rfc6585 {
+            Err(e) if limits.is_empty() => Err(e),
+            _ => Ok(Self { limits, skew: None }),
+        }
+    }
+
+    /// Extracts all rate limits from the given headers and anchors
+    /// relative reset times to the given reference instant.
+    ///
+    /// See [`RateLimit::with_anchor`]. The estimated clock skew is
+    /// available via [`RateLimits::skew`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if no rate limit could be found
+    /// in the given headers.
+    pub fn with_anchor<T: TryIntoHeaderMap>(
+        headers: T,
+        anchor: Anchor,
+    ) -> std::result::Result<Self, Error> {
+        Self::with_anchor_at(headers, anchor, &SystemClock)
+    }
+
+    /// Extracts all rate limits from the given headers and anchors
+    /// relative reset times to the given reference instant,
+    /// see [`RateLimit::with_anchor_at`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if no rate limit could be found
+    /// in the given headers.
+    pub fn with_anchor_at<T: TryIntoHeaderMap, C: Clock + ?Sized>(
+        headers: T,
+        anchor: Anchor,
+        clock: &C,
+    ) -> std::result::Result<Self, Error> {
+        let headers = headers.try_into_header_map()?;
+        let reference = anchor.instant(&headers);
+        let now = clock.now();
+        let skew = anchor.estimate_skew(&headers, now);
+        let mut rate_limits =
+            Self::collect(headers, &RATE_LIMIT_HEADERS, reference.unwrap_or(now))?;
+        for rate_limit in &mut rate_limits.limits {
+            rate_limit.anchor(reference, skew);
+        }
+        rate_limits.skew = skew;
+        Ok(rate_limits)
+    }
+
+    /// Get the estimated offset of the server clock from the local clock.
+    ///
+    /// This is only known when parsing with [`RateLimits::with_anchor`] and
+    /// the response contains a `Date` header. A positive skew means that
+    /// the server clock is ahead. It is applied by the wait time methods
+    /// of every limit, e.g. [`RateLimit::duration`].
+    #[must_use]
+    pub const fn skew(&self) -> Option<Duration> {
+        self.skew
+    }
+
+    /// Iterate over all rate limits in the order they were found
+    pub fn iter(&self) -> std::slice::Iter<'_, RateLimit> {
+        self.limits.iter()
+    }
+
+    /// Number of rate limits
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.limits.len()
+    }
+
+    /// Whether no rate limit was found
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    /// Get the rate limit which allows the fewest further requests.
+    ///
+    /// A `Retry-After` header counts as no remaining requests.
+    /// If multiple limits have the same number of remaining requests,
+    /// the one which resets last is returned. An unknown reset time counts
+    /// as the latest one.
+    #[must_use]
+    pub fn most_restrictive(&self) -> Option<&RateLimit> {
+        self.most_restrictive_at(&SystemClock)
+    }
+
+    /// Get the rate limit which allows the fewest further requests,
+    /// comparing reset times based on the current time of the given clock.
+    #[must_use]
+    pub fn most_restrictive_at<C: Clock + ?Sized>(&self, clock: &C) -> Option<&RateLimit> {
+        let now = self.server_now(clock);
+        self.limits.iter().min_by(|a, b| {
+            a.remaining()
+                .unwrap_or(0)
+                .cmp(&b.remaining().unwrap_or(0))
+                .then_with(|| match (a.reset(), b.reset()) {
+                    (Some(a), Some(b)) => b.cmp_at(&a, now),
+                    (a, b) => a.is_none().cmp(&b.is_none()).reverse(),
+                })
+        })
+    }
+
+    /// Get the earliest time at which one of the rate limits is reset.
+    ///
+    /// Rate limits without a reset time are ignored.
+    #[must_use]
+    pub fn earliest_reset(&self) -> Option<ResetTime> {
+        self.earliest_reset_at(&SystemClock)
+    }
+
+    /// Get the earliest time at which one of the rate limits is reset,
+    /// comparing reset times based on the current time of the given clock.
+    #[must_use]
+    pub fn earliest_reset_at<C: Clock + ?Sized>(&self, clock: &C) -> Option<ResetTime> {
+        let now = self.server_now(clock);
+        self.limits
+            .iter()
+            .filter_map(RateLimit::reset)
+            .min_by(|a, b| a.cmp_at(b, now))
+    }
+
+    /// Get the latest time at which one of the rate limits is reset.
+    ///
+    /// This is when all rate limits are lifted. Returns `None` if the reset
+    /// time of any rate limit is unknown.
+    #[must_use]
+    pub fn latest_reset(&self) -> Option<ResetTime> {
+        self.latest_reset_at(&SystemClock)
+    }
+
+    /// Get the latest time at which one of the rate limits is reset,
+    /// comparing reset times based on the current time of the given clock.
+    #[must_use]
+    pub fn latest_reset_at<C: Clock + ?Sized>(&self, clock: &C) -> Option<ResetTime> {
+        let now = self.server_now(clock);
+        let resets: Option<Vec<_>> = self.limits.iter().map(RateLimit::reset).collect();
+        resets?.into_iter().max_by(|a, b| a.cmp_at(b, now))
+    }
+
+    /// Get the current time of the server clock, which reset times refer to
+    fn server_now<C: Clock + ?Sized>(&self, clock: &C) -> OffsetDateTime {
+        ServerClock::new(clock, self.skew.unwrap_or(Duration::ZERO)).now()
+    }
+}
+
+impl<'a> IntoIterator for &'a RateLimits {
+    type Item = &'a RateLimit;
+    type IntoIter = std::slice::Iter<'a, RateLimit>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for RateLimits {
+    type Item = RateLimit;
+    type IntoIter = std::vec::IntoIter<RateLimit>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.limits.into_iter()
+    }
+}
+
+impl FromStr for RateLimits {
+    type Err = Error;
+
+    fn from_str(map: &str) -> Result<Self> {
+        RateLimits::new(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use indoc::indoc;
+    use time::macros::datetime;
+
+    #[test]
+    fn collect_all_limits() {
+        let headers = indoc! {r#"
+            RateLimit-Policy: "burst";q=100;w=60,"daily";q=1000;w=86400
+            RateLimit: "burst";r=50;t=30,"daily";r=900;t=3600
+            Retry-After: 10
+        "#};
+
+        let limits = RateLimits::from_str(headers).unwrap();
+        assert_eq!(limits.len(), 3);
+        assert_eq!(limits.earliest_reset(), Some(ResetTime::Seconds(10)));
+        assert_eq!(limits.latest_reset(), Some(ResetTime::Seconds(3600)));
+        assert!(matches!(
+            limits.most_restrictive(),
+            Some(RateLimit::RetryAfter(_))
+        ));
+    }
+
+    #[test]
+    fn most_restrictive_limit() {
+        let headers = indoc! {r#"
+            RateLimit: "burst";r=5;t=30,"daily";r=5;t=3600,"hourly";r=50;t=60
+        "#};
+
+        let limits = RateLimits::from_str(headers).unwrap();
+        let limit = limits.most_restrictive().unwrap();
+        assert_eq!(limit.remaining(), Some(5));
+        assert_eq!(limit.reset(), Some(ResetTime::Seconds(3600)));
+    }
+
+    #[test]
+    fn collect_vendor_and_retry_after() {
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 0
+            x-ratelimit-reset: 1350085394
+            Retry-After: 60
+        "};
+
+        let limits = RateLimits::from_str(headers).unwrap();
+        assert_eq!(limits.len(), 2);
+        assert!(matches!(
+            limits.iter().next(),
+            Some(RateLimit::Rfc6585(headers)) if headers.remaining == 0
+        ));
+    }
+
+    #[test]
+    fn collect_limits_with_registry() {
+        let variant = |name: &str| {
+            headers::RateLimitVariant::builder(headers::Vendor::Custom("Acme".to_string()))
+                .limit_header(format!("Acme-{name}-Limit"))
+                .remaining_header(format!("Acme-{name}-Remaining"))
+                .reset_header(format!("Acme-{name}-Reset"))
+                .build()
+                .unwrap()
+        };
+        let registry = [variant("Burst"), variant("Daily")].into_iter().collect();
+        let headers = indoc! {"
+            Acme-Burst-Limit: 10
+            Acme-Burst-Remaining: 5
+            Acme-Burst-Reset: 1
+            Acme-Daily-Limit: 1000
+            Acme-Daily-Remaining: 500
+            Acme-Daily-Reset: 3600
+        "};
+
+        assert!(RateLimits::from_str(headers).is_err());
+        let limits = RateLimits::with_registry(headers, &registry).unwrap();
+        assert_eq!(limits.len(), 2);
+        assert_eq!(limits.most_restrictive().unwrap().limit(), Some(10));
+    }
+
+    #[test]
+    fn anchor_all_limits() {
+        let headers = indoc! {r#"
+            Date: Wed, 01 Mar 2023 10:00:00 GMT
+            RateLimit: "burst";r=50;t=30,"daily";r=900;t=3600
+        "#};
+
+        let limits = RateLimits::with_anchor(headers, Anchor::Date).unwrap();
+        assert_eq!(
+            limits.iter().map(RateLimit::reset).collect::<Vec<_>>(),
+            vec![
+                Some(ResetTime::DateTime(datetime!(2023-03-01 10:00:30 UTC))),
+                Some(ResetTime::DateTime(datetime!(2023-03-01 11:00:00 UTC))),
+            ]
+        );
+    }
+
+    #[test]
+    fn compare_resets_at_clock() {
+        let headers = indoc! {"
+            X-Ratelimit-Used: 100
+            X-Ratelimit-Remaining: 0
+            X-Ratelimit-Reset: 30
+            Retry-After: Wed, 01 Mar 2023 10:00:20 GMT
+        "};
+        let limits = RateLimits::from_str(headers).unwrap();
+        let date = ResetTime::DateTime(datetime!(2023-03-01 10:00:20 UTC));
+
+        let clock = ManualClock::new(datetime!(2023-03-01 10:00:00 UTC));
+        assert_eq!(limits.earliest_reset_at(&clock), Some(date));
+        assert_eq!(limits.latest_reset_at(&clock), Some(ResetTime::Seconds(30)));
+        assert_eq!(
+            limits.most_restrictive_at(&clock).map(RateLimit::reset),
+            Some(Some(ResetTime::Seconds(30)))
+        );
+
+        clock.set(datetime!(2023-03-01 9:59:00 UTC));
+        assert_eq!(
+            limits.earliest_reset_at(&clock),
+            Some(ResetTime::Seconds(30))
+        );
+        assert_eq!(limits.latest_reset_at(&clock), Some(date));
+    }
+
+    #[test]
+    fn expose_clock_skew() {
+        let headers = indoc! {"
+            Date: Wed, 01 Mar 2023 09:59:00 GMT
+            Retry-After: Wed, 01 Mar 2023 10:09:00 GMT
+        "};
+
+        // The server clock is one minute behind
+        let received = datetime!(2023-03-01 10:00:00 UTC);
+        let limits = RateLimits::with_anchor(headers, Anchor::Received(received)).unwrap();
+        assert_eq!(limits.skew(), Some(Duration::minutes(-1)));
+        assert_eq!(
+            limits.earliest_reset(),
+            Some(ResetTime::DateTime(datetime!(2023-03-01 10:09:00 UTC)))
+        );
+        let clock = ManualClock::new(received);
+        let limit = limits.most_restrictive_at(&clock).unwrap();
+        assert_eq!(limit.skew(), Some(Duration::minutes(-1)));
+        assert_eq!(limit.duration_at(&clock), Some(Duration::minutes(10)));
+
+        let limits = RateLimits::from_str(headers).unwrap();
+        assert_eq!(limits.skew(), None);
+    }
+
+    #[test]
+    fn no_limits() {
+        assert!(matches!(
+            RateLimits::from_str("Content-Type: text/plain"),
+            Err(Error::MissingRemaining)
+        ));
+    }
+
+    #[test]
+    fn collect_openai_limits() {
+        let headers = indoc! {"
+            x-ratelimit-limit-requests: 60
+            x-ratelimit-limit-tokens: 150000
+            x-ratelimit-remaining-requests: 59
+            x-ratelimit-remaining-tokens: 149984
+            x-ratelimit-reset-requests: 1s
+            x-ratelimit-reset-tokens: 6m0s
+        "};
+
+        let limits = RateLimits::from_str(headers).unwrap();
+        assert_eq!(limits.len(), 2);
+        assert!(limits.iter().all(|limit| matches!(
+            limit,
+            RateLimit::Rfc6585(headers) if headers.vendor == headers::Vendor::OpenAi
+        )));
+        assert_eq!(
+            limits.iter().map(RateLimit::limit).collect::<Vec<_>>(),
+            vec![Some(60), Some(150_000)]
+        );
+        assert_eq!(
+            limits.earliest_reset(),
+            Some(ResetTime::Duration(std::time::Duration::from_secs(1)))
+        );
+        assert_eq!(
+            limits.latest_reset(),
+            Some(ResetTime::Duration(std::time::Duration::from_secs(360)))
+        );
+    }
+
+    #[test]
+    fn collect_hubspot_limits() {
+        let headers = indoc! {"
+            X-HubSpot-RateLimit-Daily: 250000
+            X-HubSpot-RateLimit-Daily-Remaining: 249999
+            X-HubSpot-RateLimit-Secondly: 10
+            X-HubSpot-RateLimit-Secondly-Remaining: 0
+        "};
+
+        let limits = RateLimits::from_str(headers).unwrap();
+        assert_eq!(limits.len(), 2);
+        // HubSpot sends no reset time, e.g. the daily limit is reset at
+        // midnight, not one day after the response
+        assert_eq!(
+            limits.iter().map(RateLimit::reset).collect::<Vec<_>>(),
+            vec![None, None]
+        );
+        assert_eq!(limits.earliest_reset(), None);
+        assert_eq!(limits.latest_reset(), None);
+        let limit = limits.most_restrictive().unwrap();
+        assert_eq!(limit.limit(), Some(10));
+        assert_eq!(limit.remaining(), Some(0));
+        assert_eq!(limit.wait(), None);
+    }
+
+    #[test]
+    fn report_invalid_ietf_rate_limit_header() {
+        let headers = indoc! {r#"
+            RateLimit: "default";r=many;t=30
+            X-RateLimit-Limit: 60
+            X-RateLimit-Remaining: 59
+            X-RateLimit-Reset: 30
+        "#};
+        assert!(matches!(
+            RateLimits::from_str(headers),
+            Err(Error::InvalidRateLimit(_))
+        ));
+    }
+}
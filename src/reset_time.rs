@@ -1,8 +1,15 @@
+use crate::casesensitive_headermap::{CaseSensitiveHeaderMap, TryIntoHeaderMap};
+use crate::clock::{Clock, SystemClock};
 use crate::convert;
+use crate::duration;
 use crate::error::{Error, Result};
+use crate::http_date;
 use headers::HeaderValue;
-use time::format_description::well_known::{Iso8601, Rfc2822};
-use time::{Duration, OffsetDateTime, PrimitiveDateTime};
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::time::Duration as StdDuration;
+use time::format_description::well_known::Iso8601;
+use time::{Duration, OffsetDateTime, PrimitiveDateTime, UtcOffset};
 
 /// The kind of rate limit reset time
 ///
@@ -16,20 +23,235 @@ pub enum ResetTimeKind {
     Seconds,
     /// Unix timestamp when rate limit will be lifted
     Timestamp,
-    /// RFC 2822 date when rate limit will be lifted
+    /// HTTP date when rate limit will be lifted
+    ///
+    /// Besides IMF-fixdate and other RFC 2822 dates, the obsolete RFC 850
+    /// and asctime formats are accepted as required by RFC 9110.
     ImfFixdate,
     /// ISO 8601 date when rate limit will be lifted
     Iso8601,
+    /// Number of seconds with a fraction until rate limit is lifted,
+    /// e.g. `1.234`
+    FractionalSeconds,
+    /// Unix timestamp with a fraction when rate limit will be lifted,
+    /// e.g. `1470173023.123`
+    FractionalTimestamp,
+    /// Unix timestamp in milliseconds when rate limit will be lifted
+    TimestampMillis,
+    /// Go duration until rate limit is lifted, e.g. `6m0s` or `17ms`
+    GoDuration,
+    /// ISO 8601 duration until rate limit is lifted, e.g. `PT30S`
+    Iso8601Duration,
+    /// Detect the kind from the value, see [`ResetTimeKind::detect`]
+    Auto,
+}
+
+impl ResetTimeKind {
+    /// Detect the kind of a reset time from its value.
+    ///
+    /// Numbers are classified by their magnitude compared to the current
+    /// unix time:
+    ///
+    /// - Integers which are closer to the current time in milliseconds
+    ///   than to the current time in seconds are
+    ///   [timestamps in milliseconds](ResetTimeKind::TimestampMillis).
+    /// - Other integers which are closer to the current time than to zero
+    ///   are [timestamps](ResetTimeKind::Timestamp). Read as seconds, they
+    ///   would be decades in the future.
+    /// - Smaller integers are [seconds](ResetTimeKind::Seconds).
+    /// - Decimals are [fractional timestamps](ResetTimeKind::FractionalTimestamp)
+    ///   or [fractional seconds](ResetTimeKind::FractionalSeconds) by the
+    ///   same rule. They are never milliseconds.
+    ///
+    /// Other values are detected by their grammar, in this order:
+    /// [Go durations](ResetTimeKind::GoDuration),
+    /// [ISO 8601 durations](ResetTimeKind::Iso8601Duration),
+    /// [HTTP dates](ResetTimeKind::ImfFixdate) and
+    /// [ISO 8601 dates](ResetTimeKind::Iso8601).
+    ///
+    /// Returns `None` if the value matches none of them.
+    ///
+    /// ```
+    /// use rate_limits::ResetTimeKind;
+    ///
+    /// assert_eq!(ResetTimeKind::detect("30"), Some(ResetTimeKind::Seconds));
+    /// assert_eq!(ResetTimeKind::detect("1350085394"), Some(ResetTimeKind::Timestamp));
+    /// assert_eq!(ResetTimeKind::detect("6m0s"), Some(ResetTimeKind::GoDuration));
+    /// assert_eq!(ResetTimeKind::detect("soon"), None);
+    /// ```
+    #[must_use]
+    pub fn detect(value: &str) -> Option<Self> {
+        Self::detect_at(value, SystemClock.now())
+    }
+
+    /// Detect the kind of a reset time relative to the given current time
+    pub(crate) fn detect_at(value: &str, now: OffsetDateTime) -> Option<Self> {
+        let value = value.trim();
+        let now = u64::try_from(now.unix_timestamp()).unwrap_or(0);
+        let is_timestamp = |seconds: u64| seconds >= now / 2;
+        if let Ok(number) = value.parse::<u64>() {
+            return Some(
+                if number >= now.saturating_mul(1000).saturating_add(now) / 2 {
+                    Self::TimestampMillis
+                } else if is_timestamp(number) {
+                    Self::Timestamp
+                } else {
+                    Self::Seconds
+                },
+            );
+        }
+        if let Ok(duration) = duration::parse_seconds(value) {
+            return Some(if is_timestamp(duration.as_secs()) {
+                Self::FractionalTimestamp
+            } else {
+                Self::FractionalSeconds
+            });
+        }
+        if duration::parse_go(value).is_ok() {
+            Some(Self::GoDuration)
+        } else if duration::parse_iso8601(value).is_ok() {
+            Some(Self::Iso8601Duration)
+        } else if http_date::parse(value).is_ok() {
+            Some(Self::ImfFixdate)
+        } else if PrimitiveDateTime::parse(value, &Iso8601::PARSING).is_ok() {
+            Some(Self::Iso8601)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for ResetTimeKind {
+    type Err = Error;
+
+    /// Parse the kind from its name, e.g. `seconds` or `imf-fixdate`
+    fn from_str(kind: &str) -> Result<Self> {
+        match kind.to_ascii_lowercase().replace('_', "-").as_str() {
+            "seconds" => Ok(Self::Seconds),
+            "timestamp" => Ok(Self::Timestamp),
+            "imf-fixdate" => Ok(Self::ImfFixdate),
+            "iso8601" | "iso-8601" => Ok(Self::Iso8601),
+            "fractional-seconds" => Ok(Self::FractionalSeconds),
+            "fractional-timestamp" => Ok(Self::FractionalTimestamp),
+            "timestamp-millis" | "timestamp-ms" => Ok(Self::TimestampMillis),
+            "go-duration" => Ok(Self::GoDuration),
+            "iso8601-duration" | "iso-8601-duration" => Ok(Self::Iso8601Duration),
+            "auto" => Ok(Self::Auto),
+            _ => Err(Error::UnknownResetKind(kind.to_string())),
+        }
+    }
+}
+
+/// Reference instant to anchor relative reset times to
+///
+/// A reset time of `Seconds(30)` is relative to the time the response was
+/// produced. When a response is processed later, e.g. after being queued,
+/// the number of seconds is stale. Anchoring converts relative reset times
+/// into absolute ones while parsing, so the remaining wait time can be
+/// computed correctly at any later point.
+///
+/// Anchored reset times are datetimes of the server clock, like the
+/// absolute reset times sent by the server. If the response contains a
+/// `Date` header, the offset of the server clock from the local clock is
+/// estimated as well, see [`Anchor::skew`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    /// Anchor to the `Date` header of the response,
+    /// which is assumed to be received now.
+    /// Reset times stay relative if the header is missing or invalid.
+    Date,
+    /// Anchor to the given instant of the local clock,
+    /// e.g. the time the response was received
+    Received(OffsetDateTime),
+}
+
+impl Anchor {
+    /// Get the reference instant of the server clock
+    /// for the given response headers
+    pub(crate) fn instant(&self, headers: &CaseSensitiveHeaderMap) -> Option<OffsetDateTime> {
+        match self {
+            Anchor::Date => date_header(headers),
+            // The `Date` header is the time of receipt on the server clock
+            Anchor::Received(received) => date_header(headers).or(Some(*received)),
+        }
+    }
+
+    /// Estimate the offset of the server clock from the local clock.
+    ///
+    /// The skew is the difference between the `Date` header of the response
+    /// and the time it was received, which is the current time for
+    /// [`Anchor::Date`]. A positive skew means that the server clock is ahead.
+    /// Returns `None` if the `Date` header is missing or invalid.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the headers cannot be parsed.
+    pub fn skew<T: TryIntoHeaderMap>(&self, headers: T) -> Result<Option<Duration>> {
+        self.skew_at(headers, &SystemClock)
+    }
+
+    /// Estimate the offset of the server clock from the given local clock,
+    /// see [`Anchor::skew`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the headers cannot be parsed.
+    pub fn skew_at<T: TryIntoHeaderMap, C: Clock + ?Sized>(
+        &self,
+        headers: T,
+        clock: &C,
+    ) -> Result<Option<Duration>> {
+        Ok(self.estimate_skew(&headers.try_into_header_map()?, clock.now()))
+    }
+
+    pub(crate) fn estimate_skew(
+        &self,
+        headers: &CaseSensitiveHeaderMap,
+        now: OffsetDateTime,
+    ) -> Option<Duration> {
+        let received = match self {
+            Anchor::Date => now,
+            Anchor::Received(received) => *received,
+        };
+        Some(date_header(headers)? - received)
+    }
+}
+
+/// Get the value of the `Date` header
+fn date_header(headers: &CaseSensitiveHeaderMap) -> Option<OffsetDateTime> {
+    let date = headers.get("Date").or_else(|| headers.get("date"))?;
+    match ResetTime::new(date, ResetTimeKind::ImfFixdate) {
+        Ok(ResetTime::DateTime(date)) => Some(date),
+        _ => None,
+    }
+}
+
+/// Parse an ISO 8601 datetime and convert it to UTC.
+///
+/// Datetimes without an offset are assumed to be in UTC.
+fn parse_iso8601(value: &str) -> Result<OffsetDateTime> {
+    let value = value.trim();
+    match OffsetDateTime::parse(value, &Iso8601::PARSING) {
+        Ok(d) => Ok(d.to_offset(UtcOffset::UTC)),
+        // The offset is optional, see https://github.com/time-rs/time/issues/378
+        Err(_) => Ok(PrimitiveDateTime::parse(value, &Iso8601::PARSING)?.assume_utc()),
+    }
 }
 
 /// Reset time of rate limiting
 ///
 /// There are different variants on how to specify reset times
 /// in rate limit headers. The most common ones are seconds and datetime.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd)]
+///
+/// Relative and absolute reset times can only be compared relative to
+/// a reference instant, so there is no `PartialOrd` implementation.
+/// Use [`ResetTime::cmp_at`] instead.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ResetTime {
     /// Number of seconds until rate limit is lifted
     Seconds(usize),
+    /// Time until rate limit is lifted, with sub-second precision
+    Duration(StdDuration),
     /// Date when rate limit will be lifted
     DateTime(OffsetDateTime),
 }
@@ -37,52 +259,609 @@ pub enum ResetTime {
 impl ResetTime {
     /// Create a new reset time from a header value and a reset time kind
     ///
+    /// For [`ResetTimeKind::Auto`], the kind is detected relative to the
+    /// current time of the system clock.
+    ///
     /// # Errors
     ///
     /// This function returns an error if the header value cannot be parsed
     /// or if the reset time kind is unknown.
     pub fn new(value: &HeaderValue, kind: ResetTimeKind) -> Result<Self> {
-        let value = value.to_str()?;
+        Self::new_at(value, kind, SystemClock.now())
+    }
+
+    /// Create a new reset time from a header value and a reset time kind,
+    /// detecting [`ResetTimeKind::Auto`] relative to the given current time
+    pub(crate) fn new_at(
+        value: &HeaderValue,
+        kind: ResetTimeKind,
+        now: OffsetDateTime,
+    ) -> Result<Self> {
+        Self::parse(value.to_str()?, kind, now)
+    }
+
+    /// Create a new reset time from a header value of unknown kind
+    ///
+    /// The kind is detected as described in [`ResetTimeKind::detect`] and
+    /// returned as well, e.g. for logging.
+    ///
+    /// ```
+    /// use headers::HeaderValue;
+    /// use rate_limits::{ResetTime, ResetTimeKind};
+    ///
+    /// let (reset, kind) = ResetTime::detect(&HeaderValue::from_static("30")).unwrap();
+    /// assert_eq!(reset, ResetTime::Seconds(30));
+    /// assert_eq!(kind, ResetTimeKind::Seconds);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the kind cannot be detected
+    /// or if the header value cannot be parsed.
+    pub fn detect(value: &HeaderValue) -> Result<(Self, ResetTimeKind)> {
+        Self::detect_at(value, SystemClock.now())
+    }
+
+    /// Create a new reset time from a header value of unknown kind,
+    /// detecting the kind relative to the given current time
+    pub(crate) fn detect_at(
+        value: &HeaderValue,
+        now: OffsetDateTime,
+    ) -> Result<(Self, ResetTimeKind)> {
+        Self::parse_detected(value.to_str()?, now)
+    }
+
+    fn parse_detected(value: &str, now: OffsetDateTime) -> Result<(Self, ResetTimeKind)> {
+        let kind = ResetTimeKind::detect_at(value, now)
+            .ok_or_else(|| Error::UndetectableResetKind(value.to_string()))?;
+        Ok((Self::parse(value, kind, now)?, kind))
+    }
+
+    fn parse(value: &str, kind: ResetTimeKind, now: OffsetDateTime) -> Result<Self> {
         match kind {
+            ResetTimeKind::Auto => Self::parse_detected(value, now).map(|(reset, _)| reset),
             ResetTimeKind::Seconds => Ok(ResetTime::Seconds(convert::to_usize(value)?)),
             ResetTimeKind::Timestamp => Ok(Self::DateTime(
                 OffsetDateTime::from_unix_timestamp(convert::to_i64(value)?)
                     .map_err(Error::Time)?,
             )),
-            ResetTimeKind::Iso8601 => {
-                // https://github.com/time-rs/time/issues/378
-                let d = PrimitiveDateTime::parse(value, &Iso8601::PARSING).map_err(Error::Parse)?;
-                Ok(ResetTime::DateTime(d.assume_utc()))
+            ResetTimeKind::Iso8601 => Ok(ResetTime::DateTime(parse_iso8601(value)?)),
+            ResetTimeKind::FractionalSeconds => {
+                Ok(ResetTime::Duration(duration::parse_seconds(value)?))
+            }
+            ResetTimeKind::FractionalTimestamp => {
+                let invalid = || Error::InvalidDuration(value.to_string());
+                let since_epoch =
+                    Duration::try_from(duration::parse_seconds(value)?).map_err(|_| invalid())?;
+                OffsetDateTime::UNIX_EPOCH
+                    .checked_add(since_epoch)
+                    .map(ResetTime::DateTime)
+                    .ok_or_else(invalid)
             }
-            ResetTimeKind::ImfFixdate => {
-                let d = PrimitiveDateTime::parse(value, &Rfc2822).map_err(Error::Parse)?;
-                Ok(ResetTime::DateTime(d.assume_utc()))
+            ResetTimeKind::GoDuration => Ok(ResetTime::Duration(duration::parse_go(value)?)),
+            ResetTimeKind::Iso8601Duration => {
+                Ok(ResetTime::Duration(duration::parse_iso8601(value)?))
             }
+            ResetTimeKind::TimestampMillis => Ok(Self::DateTime(
+                OffsetDateTime::from_unix_timestamp_nanos(
+                    i128::from(convert::to_i64(value)?) * 1_000_000,
+                )
+                .map_err(Error::Time)?,
+            )),
+            ResetTimeKind::ImfFixdate => Ok(ResetTime::DateTime(http_date::parse(value)?)),
+        }
+    }
+
+    /// Resolve the reset time to an absolute instant.
+    ///
+    /// Relative reset times are counted from the given `reference` instant,
+    /// e.g. the time at which the response was received.
+    #[must_use]
+    pub fn resolve(&self, reference: OffsetDateTime) -> OffsetDateTime {
+        match self {
+            ResetTime::Seconds(s) => i64::try_from(*s)
+                .ok()
+                .and_then(|s| reference.checked_add(Duration::seconds(s)))
+                .unwrap_or_else(|| PrimitiveDateTime::MAX.assume_utc()),
+            ResetTime::Duration(d) => Duration::try_from(*d)
+                .ok()
+                .and_then(|d| reference.checked_add(d))
+                .unwrap_or_else(|| PrimitiveDateTime::MAX.assume_utc()),
+            ResetTime::DateTime(d) => *d,
+        }
+    }
+
+    /// Anchor a relative reset time to the given instant.
+    ///
+    /// Relative reset times are converted to the datetime at which they elapse,
+    /// counted from `reference`. Datetimes are returned unchanged.
+    #[must_use]
+    pub fn anchor(self, reference: OffsetDateTime) -> Self {
+        ResetTime::DateTime(self.resolve(reference))
+    }
+
+    /// Compare two reset times by the instant at which they occur,
+    /// resolving relative reset times to the given `reference` instant.
+    #[must_use]
+    pub fn cmp_at(&self, other: &Self, reference: OffsetDateTime) -> Ordering {
+        match (self, other) {
+            (ResetTime::Seconds(a), ResetTime::Seconds(b)) => a.cmp(b),
+            (ResetTime::Duration(a), ResetTime::Duration(b)) => a.cmp(b),
+            (ResetTime::DateTime(a), ResetTime::DateTime(b)) => a.cmp(b),
+            _ => self.resolve(reference).cmp(&other.resolve(reference)),
         }
     }
 
     /// Get the number of seconds until the rate limit gets lifted.
+    ///
+    /// Returns zero if the rate limit has already been lifted.
     #[must_use]
     pub fn seconds(&self) -> usize {
+        self.seconds_at(&SystemClock)
+    }
+
+    /// Get the number of seconds until the rate limit gets lifted,
+    /// based on the current time of the given clock.
+    ///
+    /// Returns zero if the rate limit has already been lifted.
+    #[must_use]
+    pub fn seconds_at<C: Clock + ?Sized>(&self, clock: &C) -> usize {
         match self {
             ResetTime::Seconds(s) => *s,
-            // OffsetDateTime is not timezone aware, so we need to convert it to UTC
-            // and then convert it to seconds.
-            // There are no negative values in the seconds field, so we can safely
-            // cast it to usize.
-            #[allow(clippy::cast_possible_truncation)]
-            ResetTime::DateTime(d) => (*d - OffsetDateTime::now_utc()).whole_seconds() as usize,
+            ResetTime::Duration(d) => usize::try_from(d.as_secs()).unwrap_or(usize::MAX),
+            ResetTime::DateTime(d) => {
+                usize::try_from((*d - clock.now()).whole_seconds()).unwrap_or(0)
+            }
         }
     }
 
     /// Convert reset time to duration
+    ///
+    /// Returns a zero duration if the rate limit has already been lifted.
     #[must_use]
     pub fn duration(&self) -> Duration {
+        self.duration_at(&SystemClock)
+    }
+
+    /// Convert reset time to duration,
+    /// based on the current time of the given clock.
+    ///
+    /// Returns a zero duration if the rate limit has already been lifted.
+    #[must_use]
+    pub fn duration_at<C: Clock + ?Sized>(&self, clock: &C) -> Duration {
         match self {
-            ResetTime::Seconds(s) => Duration::seconds(*s as i64),
-            ResetTime::DateTime(d) => {
-                Duration::seconds((*d - OffsetDateTime::now_utc()).whole_seconds())
-            }
+            ResetTime::Seconds(s) => Duration::seconds(i64::try_from(*s).unwrap_or(i64::MAX)),
+            ResetTime::Duration(d) => Duration::try_from(*d).unwrap_or(Duration::MAX),
+            ResetTime::DateTime(d) => (*d - clock.now()).max(Duration::ZERO),
+        }
+    }
+
+    /// Get the time to wait until the rate limit gets lifted.
+    ///
+    /// The result can be passed to `std::thread::sleep` or
+    /// `tokio::time::sleep` directly via [`Wait::duration`].
+    #[must_use]
+    pub fn wait(&self) -> Wait {
+        self.wait_at(&SystemClock)
+    }
+
+    /// Get the time to wait until the rate limit gets lifted,
+    /// based on the current time of the given clock.
+    #[must_use]
+    pub fn wait_at<C: Clock + ?Sized>(&self, clock: &C) -> Wait {
+        let remaining = match self {
+            ResetTime::Seconds(s) => StdDuration::from_secs(*s as u64),
+            ResetTime::Duration(d) => *d,
+            ResetTime::DateTime(d) => match StdDuration::try_from(*d - clock.now()) {
+                Ok(remaining) => remaining,
+                // The reset time lies in the past
+                Err(_) => return Wait::Reset,
+            },
+        };
+        if remaining.is_zero() {
+            Wait::Reset
+        } else {
+            Wait::Pending(remaining)
+        }
+    }
+}
+
+/// Time to wait until a rate limit is lifted
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Wait {
+    /// The rate limit has already been lifted
+    Reset,
+    /// The rate limit will be lifted after the given duration
+    Pending(StdDuration),
+}
+
+impl Wait {
+    /// Get the duration to wait, which is zero if the rate limit
+    /// has already been lifted
+    #[must_use]
+    pub const fn duration(&self) -> StdDuration {
+        match self {
+            Wait::Reset => StdDuration::ZERO,
+            Wait::Pending(d) => *d,
+        }
+    }
+
+    /// Whether the rate limit has already been lifted
+    #[must_use]
+    pub const fn is_reset(&self) -> bool {
+        matches!(self, Wait::Reset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::str::FromStr;
+    use time::macros::datetime;
+
+    #[test]
+    fn parse_reset_time_kind() {
+        assert_eq!(
+            ResetTimeKind::from_str("imf_fixdate").unwrap(),
+            ResetTimeKind::ImfFixdate
+        );
+        assert_eq!(
+            ResetTimeKind::from_str("Seconds").unwrap(),
+            ResetTimeKind::Seconds
+        );
+        assert!(matches!(
+            ResetTimeKind::from_str("minutes"),
+            Err(Error::UnknownResetKind(_))
+        ));
+    }
+
+    #[test]
+    fn detect_reset_time_kind() {
+        let detect = |value| ResetTimeKind::detect_at(value, datetime!(2023-03-01 10:00:00 UTC));
+        assert_eq!(detect("30"), Some(ResetTimeKind::Seconds));
+        assert_eq!(detect("1677665100"), Some(ResetTimeKind::Timestamp));
+        assert_eq!(
+            detect("Wed, 01 Mar 2023 10:00:00 GMT"),
+            Some(ResetTimeKind::ImfFixdate)
+        );
+        assert_eq!(
+            detect("Wed Mar  1 10:00:00 2023"),
+            Some(ResetTimeKind::ImfFixdate)
+        );
+        assert_eq!(detect("2023-03-01T10:00:00Z"), Some(ResetTimeKind::Iso8601));
+        assert_eq!(
+            detect("1677665100123"),
+            Some(ResetTimeKind::TimestampMillis)
+        );
+        assert_eq!(detect("1.234"), Some(ResetTimeKind::FractionalSeconds));
+        assert_eq!(
+            detect("1470173023.123"),
+            Some(ResetTimeKind::FractionalTimestamp)
+        );
+        assert_eq!(detect("6m0s"), Some(ResetTimeKind::GoDuration));
+        assert_eq!(detect("PT30S"), Some(ResetTimeKind::Iso8601Duration));
+        assert_eq!(detect("soon"), None);
+        assert_eq!(detect("-1"), None);
+    }
+
+    #[test]
+    fn detect_numbers_by_magnitude() {
+        let now = datetime!(2023-03-01 10:00:00 UTC);
+        // Half of the current unix time separates seconds from timestamps
+        assert_eq!(
+            ResetTimeKind::detect_at("838832399", now),
+            Some(ResetTimeKind::Seconds)
+        );
+        assert_eq!(
+            ResetTimeKind::detect_at("838832400", now),
+            Some(ResetTimeKind::Timestamp)
+        );
+        assert_eq!(
+            ResetTimeKind::detect_at("99999999999", now),
+            Some(ResetTimeKind::Timestamp)
+        );
+        assert_eq!(
+            ResetTimeKind::detect_at("1000000000000", now),
+            Some(ResetTimeKind::TimestampMillis)
+        );
+        assert_eq!(
+            ResetTimeKind::detect_at("838832399.5", now),
+            Some(ResetTimeKind::FractionalSeconds)
+        );
+        // Stale timestamps are still timestamps
+        assert_eq!(
+            ResetTimeKind::detect_at("1262304000", now),
+            Some(ResetTimeKind::Timestamp)
+        );
+    }
+
+    #[test]
+    fn parse_auto_reset_time() {
+        let now = datetime!(2023-03-01 10:00:00 UTC);
+        let parse =
+            |value| ResetTime::new_at(&HeaderValue::from_static(value), ResetTimeKind::Auto, now);
+        assert_eq!(parse("30").unwrap(), ResetTime::Seconds(30));
+        assert_eq!(
+            parse("1350085394").unwrap(),
+            ResetTime::DateTime(datetime!(2012-10-12 23:43:14 UTC))
+        );
+        assert_eq!(
+            parse("PT1.5S").unwrap(),
+            ResetTime::Duration(StdDuration::from_millis(1500))
+        );
+        assert!(matches!(
+            parse("soon"),
+            Err(Error::UndetectableResetKind(value)) if value == "soon"
+        ));
+        // The same value is a timestamp shortly after the epoch
+        assert_eq!(
+            ResetTime::new_at(
+                &HeaderValue::from_static("1000"),
+                ResetTimeKind::Auto,
+                datetime!(1970-01-01 00:10 UTC)
+            )
+            .unwrap(),
+            ResetTime::DateTime(datetime!(1970-01-01 00:16:40 UTC))
+        );
+        assert_eq!(parse("1000").unwrap(), ResetTime::Seconds(1000));
+
+        let (reset, kind) =
+            ResetTime::detect(&HeaderValue::from_static("Wed, 01 Mar 2023 10:00:00 GMT")).unwrap();
+        assert_eq!(
+            reset,
+            ResetTime::DateTime(datetime!(2023-03-01 10:00:00 UTC))
+        );
+        assert_eq!(kind, ResetTimeKind::ImfFixdate);
+        assert_eq!(
+            ResetTimeKind::from_str("auto").unwrap(),
+            ResetTimeKind::Auto
+        );
+    }
+
+    #[test]
+    fn parse_iso8601_offsets() {
+        for (value, expected) in [
+            ("2023-03-01T10:00:00Z", datetime!(2023-03-01 10:00:00 UTC)),
+            ("2023-03-01T10:00:00", datetime!(2023-03-01 10:00:00 UTC)),
+            (
+                "2023-03-01T10:00:00+01:00",
+                datetime!(2023-03-01 09:00:00 UTC),
+            ),
+            (
+                "2023-03-01T10:00:00-0530",
+                datetime!(2023-03-01 15:30:00 UTC),
+            ),
+            ("2023-03-01T23:30:00-02", datetime!(2023-03-02 01:30:00 UTC)),
+        ] {
+            let reset = ResetTime::new(&HeaderValue::from_static(value), ResetTimeKind::Iso8601);
+            assert_eq!(reset.unwrap(), ResetTime::DateTime(expected), "{value}");
         }
     }
+
+    #[test]
+    fn parse_sub_second_reset_times() {
+        let parse = |value, kind| ResetTime::new(&HeaderValue::from_static(value), kind).unwrap();
+        assert_eq!(
+            parse("1.234", ResetTimeKind::FractionalSeconds),
+            ResetTime::Duration(StdDuration::from_millis(1234))
+        );
+        assert_eq!(
+            parse("1470173023.123", ResetTimeKind::FractionalTimestamp),
+            ResetTime::DateTime(datetime!(2016-08-02 21:23:43.123 UTC))
+        );
+        assert_eq!(
+            parse("1470173023123", ResetTimeKind::TimestampMillis),
+            ResetTime::DateTime(datetime!(2016-08-02 21:23:43.123 UTC))
+        );
+        assert_eq!(
+            ResetTimeKind::from_str("timestamp_millis").unwrap(),
+            ResetTimeKind::TimestampMillis
+        );
+        assert!(matches!(
+            ResetTime::new(
+                &HeaderValue::from_static("-1.5"),
+                ResetTimeKind::FractionalSeconds
+            ),
+            Err(Error::InvalidDuration(_))
+        ));
+        assert!(matches!(
+            ResetTime::new(
+                &HeaderValue::from_static("99999999999999999999.5"),
+                ResetTimeKind::FractionalTimestamp
+            ),
+            Err(Error::InvalidDuration(_))
+        ));
+    }
+
+    #[test]
+    fn parse_duration_reset_times() {
+        let parse = |value, kind| ResetTime::new(&HeaderValue::from_static(value), kind).unwrap();
+        assert_eq!(
+            parse("6m0s", ResetTimeKind::GoDuration),
+            ResetTime::Duration(StdDuration::from_secs(360))
+        );
+        assert_eq!(
+            parse("17ms", ResetTimeKind::GoDuration),
+            ResetTime::Duration(StdDuration::from_millis(17))
+        );
+        assert_eq!(
+            parse("PT30S", ResetTimeKind::Iso8601Duration),
+            ResetTime::Duration(StdDuration::from_secs(30))
+        );
+        assert_eq!(
+            ResetTimeKind::from_str("go_duration").unwrap(),
+            ResetTimeKind::GoDuration
+        );
+        assert!(matches!(
+            ResetTime::new(
+                &HeaderValue::from_static("30"),
+                ResetTimeKind::Iso8601Duration
+            ),
+            Err(Error::InvalidDuration(_))
+        ));
+    }
+
+    #[test]
+    fn sub_second_precision() {
+        let clock = ManualClock::new(datetime!(2023-03-01 10:00:00 UTC));
+        let reset = ResetTime::Duration(StdDuration::from_millis(1500));
+        assert_eq!(reset.seconds_at(&clock), 1);
+        assert_eq!(reset.duration_at(&clock), Duration::milliseconds(1500));
+        assert_eq!(
+            reset.wait_at(&clock),
+            Wait::Pending(StdDuration::from_millis(1500))
+        );
+        assert_eq!(
+            reset.anchor(clock.now()),
+            ResetTime::DateTime(datetime!(2023-03-01 10:00:01.5 UTC))
+        );
+        assert_eq!(
+            reset.cmp_at(&ResetTime::Seconds(1), clock.now()),
+            Ordering::Greater
+        );
+
+        let reset = ResetTime::DateTime(datetime!(2023-03-01 10:00:00.25 UTC));
+        assert_eq!(reset.duration_at(&clock), Duration::milliseconds(250));
+    }
+
+    #[test]
+    fn resolve_reset_time() {
+        let now = datetime!(2023-03-01 10:00:00 UTC);
+        assert_eq!(
+            ResetTime::Seconds(30).resolve(now),
+            datetime!(2023-03-01 10:00:30 UTC)
+        );
+        assert_eq!(
+            ResetTime::DateTime(datetime!(2015-10-21 7:28:00 UTC)).resolve(now),
+            datetime!(2015-10-21 7:28:00 UTC)
+        );
+        assert_eq!(
+            ResetTime::Seconds(usize::MAX).resolve(now),
+            PrimitiveDateTime::MAX.assume_utc()
+        );
+    }
+
+    #[test]
+    fn anchor_reset_time() {
+        let received = datetime!(2023-03-01 10:00:00 UTC);
+        assert_eq!(
+            ResetTime::Seconds(30).anchor(received),
+            ResetTime::DateTime(datetime!(2023-03-01 10:00:30 UTC))
+        );
+
+        let headers =
+            CaseSensitiveHeaderMap::from_str("Date: Wed, 01 Mar 2023 09:00:00 GMT").unwrap();
+        assert_eq!(
+            Anchor::Date.instant(&headers),
+            Some(datetime!(2023-03-01 9:00:00 UTC))
+        );
+        assert_eq!(
+            Anchor::Received(received).instant(&headers),
+            Some(datetime!(2023-03-01 9:00:00 UTC))
+        );
+        assert_eq!(
+            Anchor::Received(received).instant(&CaseSensitiveHeaderMap::new()),
+            Some(received)
+        );
+        assert_eq!(Anchor::Date.instant(&CaseSensitiveHeaderMap::new()), None);
+    }
+
+    #[test]
+    fn estimate_clock_skew() {
+        let clock = ManualClock::new(datetime!(2023-03-01 9:59:00 UTC));
+        let headers = "Date: Wed, 01 Mar 2023 10:05:00 GMT";
+        let received = datetime!(2023-03-01 10:00:00 UTC);
+
+        assert_eq!(
+            Anchor::Received(received).skew(headers).unwrap(),
+            Some(Duration::minutes(5))
+        );
+        assert_eq!(
+            Anchor::Date.skew_at(headers, &clock).unwrap(),
+            Some(Duration::minutes(6))
+        );
+        assert_eq!(
+            Anchor::Received(received).skew("Retry-After: 5").unwrap(),
+            None
+        );
+        assert!(matches!(
+            Anchor::Date.skew("Date: \u{7f}"),
+            Err(Error::InvalidHeaderLine(1, _))
+        ));
+    }
+
+    #[test]
+    fn compare_reset_times() {
+        let now = datetime!(2023-03-01 10:00:00 UTC);
+        let seconds = ResetTime::Seconds(30);
+        let past = ResetTime::DateTime(datetime!(2023-03-01 9:00:00 UTC));
+        let future = ResetTime::DateTime(datetime!(2023-03-01 11:00:00 UTC));
+
+        assert_eq!(seconds.cmp_at(&past, now), Ordering::Greater);
+        assert_eq!(seconds.cmp_at(&future, now), Ordering::Less);
+        assert_eq!(past.cmp_at(&future, now), Ordering::Less);
+        assert_eq!(
+            seconds.cmp_at(&ResetTime::Seconds(10), now),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn reset_time_at_clock() {
+        let clock = ManualClock::new(datetime!(2023-03-01 10:00:00 UTC));
+        let reset = ResetTime::DateTime(datetime!(2023-03-01 10:01:00 UTC));
+        assert_eq!(reset.seconds_at(&clock), 60);
+        assert_eq!(reset.duration_at(&clock), Duration::minutes(1));
+
+        clock.advance(Duration::seconds(45));
+        assert_eq!(reset.seconds_at(&clock), 15);
+        assert_eq!(reset.duration_at(&clock), Duration::seconds(15));
+
+        assert_eq!(ResetTime::Seconds(30).seconds_at(&clock), 30);
+        assert_eq!(
+            ResetTime::Seconds(30).duration_at(&clock),
+            Duration::seconds(30)
+        );
+    }
+
+    #[test]
+    fn saturate_past_reset_time() {
+        let clock = ManualClock::new(datetime!(2023-03-01 10:00:00 UTC));
+        let reset = ResetTime::DateTime(datetime!(2023-03-01 9:00:00 UTC));
+        assert_eq!(reset.seconds_at(&clock), 0);
+        assert_eq!(reset.duration_at(&clock), Duration::ZERO);
+        assert_eq!(reset.wait_at(&clock), Wait::Reset);
+        assert_eq!(reset.wait_at(&clock).duration(), StdDuration::ZERO);
+    }
+
+    #[test]
+    fn wait_for_reset() {
+        let clock = ManualClock::new(datetime!(2023-03-01 10:00:00 UTC));
+        let reset = ResetTime::DateTime(datetime!(2023-03-01 10:00:01.5 UTC));
+        assert_eq!(
+            reset.wait_at(&clock),
+            Wait::Pending(StdDuration::from_millis(1500))
+        );
+        assert!(!reset.wait_at(&clock).is_reset());
+
+        clock.advance(Duration::seconds(2));
+        assert!(reset.wait_at(&clock).is_reset());
+
+        assert_eq!(
+            ResetTime::Seconds(30).wait_at(&clock).duration(),
+            StdDuration::from_secs(30)
+        );
+        assert_eq!(ResetTime::Seconds(0).wait_at(&clock), Wait::Reset);
+    }
+
+    #[test]
+    fn compare_equal_instants() {
+        let now = datetime!(2023-03-01 10:00:00 UTC);
+        let seconds = ResetTime::Seconds(30);
+        let date = ResetTime::DateTime(datetime!(2023-03-01 10:00:30 UTC));
+        // Equal instants compare equal, although the values differ
+        assert_eq!(seconds.cmp_at(&date, now), Ordering::Equal);
+        assert_ne!(seconds, date);
+    }
 }
@@ -0,0 +1,533 @@
+//! Parsing and classification of HTTP responses
+//!
+//! Rate limit information is often only available as a dump of the whole
+//! response, e.g. from `curl -i`, proxy logs or packet captures.
+//! Whether a response was throttled depends on its status code as well,
+//! see [`Classifier`].
+
+use std::ops::Deref;
+use std::str::FromStr;
+use std::time::Duration;
+
+use http::StatusCode;
+
+use crate::casesensitive_headermap::{raw_lines, CaseSensitiveHeaderMap, TryIntoHeaderMap};
+use crate::clock::{Clock, SystemClock};
+use crate::error::{Error, Result};
+use crate::headers::{VariantRegistry, RATE_LIMIT_HEADERS};
+use crate::reset_time::ResetTime;
+use crate::{retryafter, RateLimit, RateLimits};
+
+/// Status code and headers of an HTTP/1.x response
+///
+/// The casing of header names is kept, so vendors can be detected reliably.
+///
+/// ```
+/// use rate_limits::response::ResponseHead;
+/// use rate_limits::ResetTime;
+///
+/// let raw = "HTTP/1.1 429 Too Many Requests\r\n\
+///     Content-Type: text/plain\r\n\
+///     Retry-After: 30\r\n\
+///     \r\n\
+///     Slow down!";
+///
+/// let response: ResponseHead = raw.parse().unwrap();
+/// assert!(response.is_too_many_requests());
+///
+/// let rate_limit = response.rate_limit().unwrap();
+/// assert!(rate_limit.is_throttled());
+/// assert_eq!(rate_limit.reset(), Some(ResetTime::Seconds(30)));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResponseHead {
+    /// Status code of the response
+    pub status: StatusCode,
+    /// Headers of the response
+    pub headers: CaseSensitiveHeaderMap,
+}
+
+impl ResponseHead {
+    /// Parse a raw HTTP/1.x response.
+    ///
+    /// Parsing starts at the status line and stops at the empty line which
+    /// separates headers and body, so the body is ignored. Both CRLF and LF
+    /// line endings are accepted. Interim responses with a `1xx` status,
+    /// e.g. `100 Continue`, are skipped.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the status line is missing or
+    /// invalid, or if a header cannot be parsed.
+    pub fn parse(raw: &[u8]) -> Result<Self> {
+        let mut lines = raw_lines(raw);
+        loop {
+            let (_, line) = lines
+                .by_ref()
+                .find(|(_, line)| !line.is_empty())
+                .ok_or_else(|| Error::InvalidStatusLine(String::new()))?;
+            let status = parse_status_line(line)?;
+            let headers = CaseSensitiveHeaderMap::parse_block(&mut lines)?;
+            if !status.is_informational() {
+                return Ok(Self { status, headers });
+            }
+        }
+    }
+
+    /// Get the status code of the response
+    #[must_use]
+    pub const fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Get the headers of the response
+    #[must_use]
+    pub const fn headers(&self) -> &CaseSensitiveHeaderMap {
+        &self.headers
+    }
+
+    /// Whether the status is `429 Too Many Requests`
+    #[must_use]
+    pub fn is_too_many_requests(&self) -> bool {
+        self.status == StatusCode::TOO_MANY_REQUESTS
+    }
+
+    /// Whether the status is `503 Service Unavailable`
+    #[must_use]
+    pub fn is_service_unavailable(&self) -> bool {
+        self.status == StatusCode::SERVICE_UNAVAILABLE
+    }
+
+    /// Get the rate limit of the response together with its status code,
+    /// see [`RateLimit::new`]
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if no rate limit could be found
+    /// in the headers.
+    pub fn rate_limit(&self) -> Result<ResponseRateLimit> {
+        Ok(ResponseRateLimit {
+            status: self.status,
+            rate_limit: RateLimit::new(self)?,
+        })
+    }
+
+    /// Classify the response with the default [`Classifier`]
+    #[must_use]
+    pub fn classify(&self) -> Classification {
+        Classifier::new().classify_parsed(self.status, self.headers.clone())
+    }
+
+    /// Get all rate limits of the response, see [`RateLimits::new`]
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if no rate limit could be found
+    /// in the headers.
+    pub fn rate_limits(&self) -> Result<RateLimits> {
+        RateLimits::new(self)
+    }
+}
+
+/// A rate limit together with the status code of its response
+///
+/// Whether the rate limit was exceeded depends on the status code,
+/// so it is kept alongside. Dereferences to the [`RateLimit`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResponseRateLimit {
+    /// Status code of the response
+    pub status: StatusCode,
+    /// Rate limit parsed from the headers of the response
+    pub rate_limit: RateLimit,
+}
+
+impl ResponseRateLimit {
+    /// Get the status code of the response
+    #[must_use]
+    pub const fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Get the rate limit
+    #[must_use]
+    pub const fn rate_limit(&self) -> &RateLimit {
+        &self.rate_limit
+    }
+
+    /// Convert into the rate limit, dropping the status code
+    #[must_use]
+    pub fn into_rate_limit(self) -> RateLimit {
+        self.rate_limit
+    }
+
+    /// Whether the request was throttled.
+    ///
+    /// This is the case for `429 Too Many Requests`, and for `403 Forbidden`
+    /// if no quota remains, as some APIs like GitHub use it for exceeded
+    /// rate limits.
+    #[must_use]
+    pub fn is_throttled(&self) -> bool {
+        self.status == StatusCode::TOO_MANY_REQUESTS
+            || self.status == StatusCode::FORBIDDEN && self.rate_limit.remaining() == Some(0)
+    }
+}
+
+impl Deref for ResponseRateLimit {
+    type Target = RateLimit;
+
+    fn deref(&self) -> &RateLimit {
+        &self.rate_limit
+    }
+}
+
+/// Rate limit state of a response, based on its status code and headers
+#[derive(Clone, Debug, PartialEq)]
+pub enum Classification {
+    /// The request was rejected because a rate limit was exceeded.
+    ///
+    /// Wait until the reset time of the rate limit before retrying.
+    Throttled(RateLimit),
+    /// The service is unavailable (`503 Service Unavailable`).
+    ///
+    /// Contains the time to retry if the server sent a `Retry-After` header.
+    Unavailable(Option<RateLimit>),
+    /// The request was accepted and the response contains the remaining quota
+    Quota(RateLimit),
+    /// The request was not throttled and the response contains no quota
+    Unknown,
+}
+
+impl Classification {
+    /// Whether the request was throttled
+    #[must_use]
+    pub const fn is_throttled(&self) -> bool {
+        matches!(self, Self::Throttled(_))
+    }
+
+    /// Get the rate limit, if any
+    #[must_use]
+    pub const fn rate_limit(&self) -> Option<&RateLimit> {
+        match self {
+            Self::Throttled(rate_limit) | Self::Quota(rate_limit) => Some(rate_limit),
+            Self::Unavailable(rate_limit) => rate_limit.as_ref(),
+            Self::Unknown => None,
+        }
+    }
+}
+
+/// Classifies responses by their status code and rate limit headers
+///
+/// - `429 Too Many Requests` is [`Classification::Throttled`].
+///   If the response has no usable rate limit headers,
+///   the [default backoff](Classifier::default_backoff) is used.
+/// - `403 Forbidden` with no remaining quota is throttled as well,
+///   as some APIs like GitHub use it for exceeded rate limits.
+/// - `503 Service Unavailable` is [`Classification::Unavailable`].
+/// - Any other status is [`Classification::Quota`] if the response contains
+///   quota headers. `Retry-After` is ignored for these, as it has
+///   a different meaning e.g. for redirects.
+///
+/// ```
+/// use std::time::Duration;
+/// use http::{Response, StatusCode};
+/// use rate_limits::response::{Classification, Classifier};
+/// use rate_limits::ResetTime;
+///
+/// let classifier = Classifier::new().default_backoff(Duration::from_secs(5));
+///
+/// let response = Response::builder().status(429).body(()).unwrap();
+/// let classification = classifier.classify_response(&response).unwrap();
+/// assert!(classification.is_throttled());
+/// assert_eq!(
+///     classification.rate_limit().unwrap().reset(),
+///     Some(ResetTime::Seconds(5))
+/// );
+///
+/// let classification = classifier.classify(StatusCode::OK, "Retry-After: 30").unwrap();
+/// assert_eq!(classification, Classification::Unknown);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Classifier {
+    default_backoff: Duration,
+    /// Vendor variants to consult, the built-in ones if unset
+    registry: Option<VariantRegistry>,
+}
+
+impl Default for Classifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Classifier {
+    /// Default backoff for throttled responses without rate limit headers
+    pub const DEFAULT_BACKOFF: Duration = Duration::from_secs(60);
+
+    /// Create a classifier with a default backoff of one minute
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            default_backoff: Self::DEFAULT_BACKOFF,
+            registry: None,
+        }
+    }
+
+    /// Set the time to wait after a throttled response
+    /// without usable rate limit headers
+    #[must_use]
+    pub const fn default_backoff(mut self, backoff: Duration) -> Self {
+        self.default_backoff = backoff;
+        self
+    }
+
+    /// Set the registry of vendor variants to consult
+    /// instead of the built-in variants
+    #[must_use]
+    pub fn registry(mut self, registry: VariantRegistry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Classify a response by its status code and headers
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the headers cannot be parsed.
+    /// Missing or invalid rate limit headers are not an error.
+    pub fn classify<T: TryIntoHeaderMap>(
+        &self,
+        status: StatusCode,
+        headers: T,
+    ) -> Result<Classification> {
+        Ok(self.classify_parsed(status, headers.try_into_header_map()?))
+    }
+
+    /// Classify an [`http::Response`]
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the headers cannot be parsed.
+    pub fn classify_response<B>(&self, response: &http::Response<B>) -> Result<Classification> {
+        self.classify(response.status(), response.headers())
+    }
+
+    fn classify_parsed(
+        &self,
+        status: StatusCode,
+        headers: CaseSensitiveHeaderMap,
+    ) -> Classification {
+        let registry = self.registry.as_ref().unwrap_or(&RATE_LIMIT_HEADERS);
+        let now = SystemClock.now();
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => Classification::Throttled(
+                RateLimit::select(headers, now, registry).unwrap_or_else(|_| self.backoff()),
+            ),
+            StatusCode::SERVICE_UNAVAILABLE => Classification::Unavailable(
+                retryafter::RateLimit::new(headers)
+                    .ok()
+                    .map(RateLimit::RetryAfter),
+            ),
+            _ => match RateLimit::quota(headers, registry, now) {
+                Ok(rate_limit)
+                    if status == StatusCode::FORBIDDEN && rate_limit.remaining() == Some(0) =>
+                {
+                    Classification::Throttled(rate_limit)
+                }
+                Ok(rate_limit) => Classification::Quota(rate_limit),
+                Err(_) => Classification::Unknown,
+            },
+        }
+    }
+
+    /// Rate limit for throttled responses without usable headers
+    fn backoff(&self) -> RateLimit {
+        let seconds = usize::try_from(self.default_backoff.as_secs()).unwrap_or(usize::MAX);
+        RateLimit::RetryAfter(retryafter::RateLimit {
+            reset: ResetTime::Seconds(seconds),
+            warnings: Vec::new(),
+            skew: None,
+        })
+    }
+}
+
+/// Parse a status line like `HTTP/1.1 429 Too Many Requests`
+fn parse_status_line(line: &[u8]) -> Result<StatusCode> {
+    let invalid = || Error::InvalidStatusLine(String::from_utf8_lossy(line).into());
+    let mut parts = line.split(|&b| b == b' ').filter(|part| !part.is_empty());
+    match (parts.next(), parts.next()) {
+        (Some(version), Some(code)) if version.starts_with(b"HTTP/") && code.len() == 3 => {
+            StatusCode::from_bytes(code).map_err(|_| invalid())
+        }
+        _ => Err(invalid()),
+    }
+}
+
+impl FromStr for ResponseHead {
+    type Err = Error;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        ResponseHead::parse(raw.as_bytes())
+    }
+}
+
+impl From<ResponseHead> for CaseSensitiveHeaderMap {
+    fn from(response: ResponseHead) -> Self {
+        response.headers
+    }
+}
+
+impl From<&ResponseHead> for CaseSensitiveHeaderMap {
+    fn from(response: &ResponseHead) -> Self {
+        response.headers.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::RateLimitVariant;
+    use crate::{ResetTime, Vendor};
+    use indoc::indoc;
+
+    #[test]
+    fn parse_curl_output() {
+        let raw = indoc! {"
+            HTTP/1.1 100 Continue
+
+            HTTP/2 503
+            date: Wed, 01 Mar 2023 10:00:00 GMT
+            retry-after: 120
+
+            <html>Down for maintenance: back soon</html>
+        "};
+
+        let response = ResponseHead::from_str(raw).unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.is_service_unavailable());
+        assert!(!response.is_too_many_requests());
+        assert!(response
+            .headers()
+            .get("<html>Down for maintenance")
+            .is_none());
+        let rate_limit = response.rate_limit().unwrap();
+        assert_eq!(rate_limit.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!rate_limit.is_throttled());
+        assert_eq!(rate_limit.reset(), Some(ResetTime::Seconds(120)));
+    }
+
+    #[test]
+    fn parse_folded_headers() {
+        let raw = b"HTTP/1.1 429 Too Many Requests\r\n\
+            RateLimit-Policy: \"burst\";q=100;w=60,\r\n\
+            \t\"daily\";q=1000;w=86400\r\n\
+            RateLimit: \"burst\";r=0;t=30,\r\n \"daily\";r=10;t=3600\r\n\
+            \r\n";
+
+        let response = ResponseHead::parse(raw).unwrap();
+        assert!(response.is_too_many_requests());
+        assert!(response.rate_limit().unwrap().is_throttled());
+        let limits = response.rate_limits().unwrap();
+        assert_eq!(limits.len(), 2);
+        assert_eq!(
+            limits.most_restrictive().unwrap().reset(),
+            Some(ResetTime::Seconds(30))
+        );
+    }
+
+    #[test]
+    fn classify_throttled_responses() {
+        let classifier = Classifier::new();
+        let classification = classifier
+            .classify(StatusCode::TOO_MANY_REQUESTS, "Retry-After: 30")
+            .unwrap();
+        assert_eq!(
+            classification.rate_limit().unwrap().reset(),
+            Some(ResetTime::Seconds(30))
+        );
+
+        let classification = classifier
+            .classify(StatusCode::TOO_MANY_REQUESTS, "Content-Type: text/plain")
+            .unwrap();
+        assert!(classification.is_throttled());
+        assert_eq!(
+            classification.rate_limit().unwrap().reset(),
+            Some(ResetTime::Seconds(60))
+        );
+
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 0
+            x-ratelimit-reset: 1350085394
+        "};
+        let classification = classifier.classify(StatusCode::FORBIDDEN, headers).unwrap();
+        assert!(classification.is_throttled());
+        let classification = classifier.classify(StatusCode::OK, headers).unwrap();
+        assert!(matches!(classification, Classification::Quota(_)));
+    }
+
+    #[test]
+    fn classify_with_custom_registry() {
+        let acme = RateLimitVariant::builder(Vendor::Custom("Acme".to_string()))
+            .limit_header("Acme-Limit")
+            .remaining_header("Acme-Remaining")
+            .reset_header("Acme-Reset")
+            .build()
+            .unwrap();
+        let headers = "Acme-Limit: 10\nAcme-Remaining: 0\nAcme-Reset: 30";
+
+        let classification = Classifier::new()
+            .classify(StatusCode::FORBIDDEN, headers)
+            .unwrap();
+        assert_eq!(classification, Classification::Unknown);
+
+        let classifier = Classifier::new().registry(std::iter::once(acme).collect());
+        let classification = classifier.classify(StatusCode::FORBIDDEN, headers).unwrap();
+        assert!(classification.is_throttled());
+        assert_eq!(
+            classification.rate_limit().unwrap().reset(),
+            Some(ResetTime::Seconds(30))
+        );
+    }
+
+    #[test]
+    fn classify_unavailable_responses() {
+        let classifier = Classifier::new();
+        let classification = classifier
+            .classify(StatusCode::SERVICE_UNAVAILABLE, "Retry-After: 120")
+            .unwrap();
+        assert!(matches!(
+            classification,
+            Classification::Unavailable(Some(RateLimit::RetryAfter(_)))
+        ));
+        assert_eq!(
+            classifier
+                .classify(StatusCode::SERVICE_UNAVAILABLE, "Content-Type: text/plain")
+                .unwrap(),
+            Classification::Unavailable(None)
+        );
+
+        let response =
+            ResponseHead::from_str("HTTP/1.1 301 Moved Permanently\nRetry-After: 30").unwrap();
+        assert_eq!(response.classify(), Classification::Unknown);
+    }
+
+    #[test]
+    fn parse_invalid_responses() {
+        assert!(matches!(
+            ResponseHead::from_str(""),
+            Err(Error::InvalidStatusLine(_))
+        ));
+        assert!(matches!(
+            ResponseHead::from_str("Retry-After: 30"),
+            Err(Error::InvalidStatusLine(_))
+        ));
+        assert!(matches!(
+            ResponseHead::from_str("HTTP/1.1 4290 Too Many Requests"),
+            Err(Error::InvalidStatusLine(_))
+        ));
+        assert!(matches!(
+            ResponseHead::from_str("HTTP/1.1 429\r\nRetry-After 30\r\n"),
+            Err(Error::InvalidHeaderLine(2, _))
+        ));
+    }
+}
@@ -3,32 +3,39 @@
 //! See <https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Retry-After>
 use std::str::FromStr;
 
-use headers::HeaderValue;
-use time::{format_description::well_known::Rfc2822, Date};
-
 use crate::{
-    casesensitive_headermap::CaseSensitiveHeaderMap,
+    casesensitive_headermap::{singleton, CaseSensitiveHeaderMap, TryIntoHeaderMap},
+    http_date,
     reset_time::{ResetTime, ResetTimeKind},
 };
+use headers::HeaderValue;
+use time::Duration;
 
-use super::error::{Error, Result};
+use super::error::{Error, Result, Warning};
 
 /// HTTP rate limits as parsed from header values
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct RateLimit {
     /// Time at which the rate limit will be reset
     pub reset: ResetTime,
+    /// Problems found while parsing, e.g. conflicting `Retry-After` values
+    pub warnings: Vec<Warning>,
+    /// Estimated offset of the server clock from the local clock
+    pub(crate) skew: Option<Duration>,
 }
 
 impl RateLimit {
     /// Rate limit implementation based on `Retry-After` header value
     ///
     /// See <https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Retry-After>
-    pub fn new<T: Into<CaseSensitiveHeaderMap>>(headers: T) -> std::result::Result<Self, Error> {
-        let headers = headers.into();
-        let reset = match Self::get_retry_after_header(&headers) {
+    pub fn new<T: TryIntoHeaderMap>(headers: T) -> std::result::Result<Self, Error> {
+        let headers = headers.try_into_header_map()?;
+        let mut warnings = Vec::new();
+        let retry_after = Self::get_retry_after_header(&headers)
+            .and_then(|values| singleton("Retry-After", values, &mut warnings));
+        let reset = match retry_after {
             Some(retry_after) => {
-                if Date::parse(retry_after.to_str()?, &Rfc2822).is_ok() {
+                if http_date::parse(retry_after.to_str()?).is_ok() {
                     ResetTime::new(retry_after, ResetTimeKind::ImfFixdate)?
                 } else {
                     ResetTime::new(retry_after, ResetTimeKind::Seconds)?
@@ -37,17 +44,21 @@ impl RateLimit {
             None => return Err(Error::MissingRetryAfter),
         };
 
-        Ok(RateLimit { reset })
+        Ok(RateLimit {
+            reset,
+            warnings,
+            skew: None,
+        })
     }
 
     /// Get the Retry-After header value
     ///
     /// This does not need to be case sensitive because the header name is
     /// not ambiguous.
-    fn get_retry_after_header(header_map: &CaseSensitiveHeaderMap) -> Option<&HeaderValue> {
+    fn get_retry_after_header(header_map: &CaseSensitiveHeaderMap) -> Option<&[HeaderValue]> {
         header_map
-            .get("Retry-After")
-            .or_else(|| header_map.get("retry-after"))
+            .get_all("Retry-After")
+            .or_else(|| header_map.get_all("retry-after"))
     }
 
     /// Get the time at which the rate limit will be reset
@@ -55,6 +66,19 @@ impl RateLimit {
     pub const fn reset(&self) -> ResetTime {
         self.reset
     }
+
+    /// Get the problems found while parsing
+    #[must_use]
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Get the estimated offset of the server clock from the local clock,
+    /// see [`Anchor::skew`](crate::Anchor::skew)
+    #[must_use]
+    pub const fn skew(&self) -> Option<Duration> {
+        self.skew
+    }
 }
 
 impl FromStr for RateLimit {
@@ -77,7 +101,7 @@ mod tests {
         let map = CaseSensitiveHeaderMap::from_str("Retry-After: 30").unwrap();
         let retry = RateLimit::get_retry_after_header(&map).unwrap();
 
-        assert_eq!("30", retry);
+        assert_eq!(retry, ["30"]);
     }
 
     #[test]
@@ -100,6 +124,24 @@ mod tests {
         assert_eq!(rate.reset(), ResetTime::Seconds(19));
     }
 
+    #[test]
+    fn retry_after_conflicting_values() {
+        let headers = indoc! {"
+            Retry-After: 19
+            Retry-After: 20
+        "};
+
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(rate.reset(), ResetTime::Seconds(19));
+        assert!(matches!(
+            rate.warnings(),
+            [Warning::ConflictingValues(name, _)] if name == "Retry-After"
+        ));
+
+        let rate = RateLimit::from_str("Retry-After: 19\nRetry-After: 19").unwrap();
+        assert!(rate.warnings().is_empty());
+    }
+
     #[test]
     fn retry_after_imf_fixdate() {
         let headers = indoc! {"
@@ -112,4 +154,18 @@ mod tests {
             ResetTime::DateTime(datetime!(1999-12-31 23:59:59 UTC))
         );
     }
+
+    #[test]
+    fn retry_after_obsolete_http_dates() {
+        for headers in [
+            "Retry-After: Sunday, 06-Nov-94 08:49:37 GMT",
+            "Retry-After: Sun Nov  6 08:49:37 1994",
+        ] {
+            let rate = RateLimit::from_str(headers).unwrap();
+            assert_eq!(
+                rate.reset(),
+                ResetTime::DateTime(datetime!(1994-11-06 08:49:37 UTC))
+            );
+        }
+    }
 }
@@ -0,0 +1,755 @@
+//! Structured Field Values for HTTP as defined in [RFC 8941][rfc8941]
+//!
+//! Several rate limit headers use Structured Field syntax, for example
+//! the `RateLimit` and `RateLimit-Policy` headers of the IETF draft
+//! (`"burst";q=100;w=60`) or quota policies like `100;w=60`.
+//! This module provides a parser for Items, Lists and Dictionaries,
+//! which is shared by the header parsers of this crate.
+//!
+//! ```
+//! use rate_limits::structured::{self, BareItem};
+//!
+//! let list = structured::parse_list(r#""burst";q=100;w=60, "daily";q=1000"#).unwrap();
+//! let burst = list[0].as_item().unwrap();
+//! assert_eq!(burst.bare_item.as_str(), Some("burst"));
+//! assert_eq!(burst.params.get("w"), Some(&BareItem::Integer(60)));
+//! ```
+//!
+//! [rfc8941]: https://www.rfc-editor.org/rfc/rfc8941.html
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+
+use crate::error::{Error, Result};
+
+/// Byte sequences are decoded leniently, as permitted by RFC 8941
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+/// Maximum number of digits in an integer
+const MAX_INTEGER_DIGITS: usize = 15;
+/// Maximum number of digits before the decimal point
+const MAX_DECIMAL_INTEGER_DIGITS: usize = 12;
+/// Maximum number of digits after the decimal point
+const MAX_DECIMAL_FRACTION_DIGITS: usize = 3;
+
+/// A bare item value without parameters
+#[derive(Clone, Debug, PartialEq)]
+pub enum BareItem {
+    /// An integer, e.g. `42`
+    Integer(i64),
+    /// A decimal number, e.g. `4.5`
+    Decimal(f64),
+    /// A string, e.g. `"burst"`
+    String(String),
+    /// A token, e.g. `burst`
+    Token(String),
+    /// A byte sequence, e.g. `:cHJldGVuZA==:`
+    ByteSequence(Vec<u8>),
+    /// A boolean, e.g. `?1`
+    Boolean(bool),
+}
+
+impl BareItem {
+    /// Get the value of an integer item
+    #[must_use]
+    pub const fn as_integer(&self) -> Option<i64> {
+        match self {
+            Self::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a numeric item as a decimal
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub const fn as_decimal(&self) -> Option<f64> {
+        match self {
+            Self::Decimal(d) => Some(*d),
+            Self::Integer(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a string or token item
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) | Self::Token(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Get the decoded value of a byte sequence item
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::ByteSequence(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a boolean item
+    #[must_use]
+    pub const fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+/// Ordered parameters of an item or inner list, e.g. `;q=100;w=60`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Parameters(Vec<(String, BareItem)>);
+
+impl Parameters {
+    /// Get the value of a parameter
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&BareItem> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Iterate over all parameters in order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &BareItem)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Number of parameters
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no parameters
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Insert a parameter, replacing the value of an existing key in place
+    fn insert(&mut self, key: String, value: BareItem) {
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.0.push((key, value)),
+        }
+    }
+}
+
+/// An item with parameters, e.g. `"burst";q=100`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item {
+    /// The value of the item
+    pub bare_item: BareItem,
+    /// Parameters of the item
+    pub params: Parameters,
+}
+
+/// An inner list with parameters, e.g. `(1 2 3);a=1`
+#[derive(Clone, Debug, PartialEq)]
+pub struct InnerList {
+    /// Items of the inner list
+    pub items: Vec<Item>,
+    /// Parameters of the inner list
+    pub params: Parameters,
+}
+
+/// A member of a list or dictionary
+#[derive(Clone, Debug, PartialEq)]
+pub enum ListEntry {
+    /// A single item
+    Item(Item),
+    /// An inner list of items
+    InnerList(InnerList),
+}
+
+impl ListEntry {
+    /// Get the entry as an item, unless it is an inner list
+    #[must_use]
+    pub const fn as_item(&self) -> Option<&Item> {
+        match self {
+            Self::Item(item) => Some(item),
+            Self::InnerList(_) => None,
+        }
+    }
+
+    /// Get the parameters of the entry
+    #[must_use]
+    pub const fn params(&self) -> &Parameters {
+        match self {
+            Self::Item(item) => &item.params,
+            Self::InnerList(list) => &list.params,
+        }
+    }
+}
+
+/// A list of items and inner lists
+pub type List = Vec<ListEntry>;
+
+/// An ordered map of keys to items or inner lists, e.g. `a=1, b;x=2`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dictionary(Vec<(String, ListEntry)>);
+
+impl Dictionary {
+    /// Get the member for a key
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&ListEntry> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Iterate over all members in order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ListEntry)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Number of members
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no members
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Insert a member, replacing the value of an existing key in place
+    fn insert(&mut self, key: String, value: ListEntry) {
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.0.push((key, value)),
+        }
+    }
+}
+
+/// Parse a structured field value of type Item
+///
+/// # Errors
+///
+/// This function returns an error if the value is not a valid Item.
+pub fn parse_item(value: &str) -> Result<Item> {
+    Parser::new(value).parse(Parser::parse_item)
+}
+
+/// Parse a structured field value of type List
+///
+/// # Errors
+///
+/// This function returns an error if the value is not a valid List.
+pub fn parse_list(value: &str) -> Result<List> {
+    Parser::new(value).parse(Parser::parse_list)
+}
+
+/// Parse a structured field value of type Dictionary
+///
+/// # Errors
+///
+/// This function returns an error if the value is not a valid Dictionary.
+pub fn parse_dictionary(value: &str) -> Result<Dictionary> {
+    Parser::new(value).parse(Parser::parse_dictionary)
+}
+
+/// Parser state as described in section 4.2 of RFC 8941
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    const fn new(input: &'a str) -> Self {
+        Self {
+            input: input.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    /// Run a top-level parsing function and make sure all input is consumed
+    fn parse<T>(mut self, f: fn(&mut Self) -> Result<T>) -> Result<T> {
+        self.skip_sp();
+        let value = f(&mut self)?;
+        self.skip_sp();
+        match self.peek() {
+            None => Ok(value),
+            Some(c) => Err(self.unexpected(c)),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn skip_sp(&mut self) {
+        while self.peek() == Some(b' ') {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_ows(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<()> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => {
+                self.pos -= 1;
+                Err(self.unexpected(c))
+            }
+            None => Err(Error::UnexpectedEnd),
+        }
+    }
+
+    fn unexpected(&self, c: u8) -> Error {
+        Error::UnexpectedCharacter(char::from(c), self.pos)
+    }
+
+    fn parse_list(&mut self) -> Result<List> {
+        let mut members = Vec::new();
+        while self.peek().is_some() {
+            members.push(self.parse_list_entry()?);
+            self.skip_ows();
+            match self.next() {
+                None => return Ok(members),
+                Some(b',') => {}
+                Some(c) => {
+                    self.pos -= 1;
+                    return Err(self.unexpected(c));
+                }
+            }
+            self.skip_ows();
+            if self.peek().is_none() {
+                // Trailing comma
+                return Err(Error::UnexpectedEnd);
+            }
+        }
+        Ok(members)
+    }
+
+    fn parse_dictionary(&mut self) -> Result<Dictionary> {
+        let mut dictionary = Dictionary::default();
+        while self.peek().is_some() {
+            let key = self.parse_key()?;
+            let member = if self.peek() == Some(b'=') {
+                self.pos += 1;
+                self.parse_list_entry()?
+            } else {
+                ListEntry::Item(Item {
+                    bare_item: BareItem::Boolean(true),
+                    params: self.parse_parameters()?,
+                })
+            };
+            dictionary.insert(key, member);
+            self.skip_ows();
+            match self.next() {
+                None => return Ok(dictionary),
+                Some(b',') => {}
+                Some(c) => {
+                    self.pos -= 1;
+                    return Err(self.unexpected(c));
+                }
+            }
+            self.skip_ows();
+            if self.peek().is_none() {
+                // Trailing comma
+                return Err(Error::UnexpectedEnd);
+            }
+        }
+        Ok(dictionary)
+    }
+
+    fn parse_list_entry(&mut self) -> Result<ListEntry> {
+        if self.peek() == Some(b'(') {
+            self.parse_inner_list().map(ListEntry::InnerList)
+        } else {
+            self.parse_item().map(ListEntry::Item)
+        }
+    }
+
+    fn parse_inner_list(&mut self) -> Result<InnerList> {
+        self.expect(b'(')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_sp();
+            match self.peek() {
+                Some(b')') => {
+                    self.pos += 1;
+                    let params = self.parse_parameters()?;
+                    return Ok(InnerList { items, params });
+                }
+                Some(_) => {
+                    items.push(self.parse_item()?);
+                    match self.peek() {
+                        Some(b' ' | b')') => {}
+                        Some(c) => return Err(self.unexpected(c)),
+                        None => return Err(Error::UnexpectedEnd),
+                    }
+                }
+                None => return Err(Error::UnexpectedEnd),
+            }
+        }
+    }
+
+    fn parse_item(&mut self) -> Result<Item> {
+        let bare_item = self.parse_bare_item()?;
+        let params = self.parse_parameters()?;
+        Ok(Item { bare_item, params })
+    }
+
+    fn parse_bare_item(&mut self) -> Result<BareItem> {
+        match self.peek() {
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(b'"') => self.parse_string(),
+            Some(b':') => self.parse_byte_sequence(),
+            Some(b'?') => self.parse_boolean(),
+            Some(c) if c.is_ascii_alphabetic() || c == b'*' => self.parse_token(),
+            Some(c) => Err(self.unexpected(c)),
+            None => Err(Error::UnexpectedEnd),
+        }
+    }
+
+    fn parse_parameters(&mut self) -> Result<Parameters> {
+        let mut params = Parameters::default();
+        while self.peek() == Some(b';') {
+            self.pos += 1;
+            self.skip_sp();
+            let key = self.parse_key()?;
+            let value = if self.peek() == Some(b'=') {
+                self.pos += 1;
+                self.parse_bare_item()?
+            } else {
+                BareItem::Boolean(true)
+            };
+            params.insert(key, value);
+        }
+        Ok(params)
+    }
+
+    fn parse_key(&mut self) -> Result<String> {
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if c.is_ascii_lowercase() || c == b'*' => self.pos += 1,
+            Some(c) => return Err(self.unexpected(c)),
+            None => return Err(Error::UnexpectedEnd),
+        }
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_lowercase() || c.is_ascii_digit() || b"_-.*".contains(&c)) {
+                break;
+            }
+            self.pos += 1;
+        }
+        Ok(self.slice(start).to_string())
+    }
+
+    fn parse_number(&mut self) -> Result<BareItem> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        let digits_start = self.pos;
+        let mut decimal_point = None;
+        while let Some(c) = self.peek() {
+            match c {
+                b'0'..=b'9' => {}
+                b'.' if decimal_point.is_none() => decimal_point = Some(self.pos),
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        let number = self.slice(start);
+
+        match decimal_point {
+            None => {
+                let digits = self.pos - digits_start;
+                if digits == 0 || digits > MAX_INTEGER_DIGITS {
+                    return Err(Error::InvalidInteger(number.to_string()));
+                }
+                Ok(BareItem::Integer(number.parse()?))
+            }
+            Some(point) => {
+                let integer_digits = point - digits_start;
+                let fraction_digits = self.pos - point - 1;
+                if integer_digits == 0
+                    || integer_digits > MAX_DECIMAL_INTEGER_DIGITS
+                    || fraction_digits == 0
+                    || fraction_digits > MAX_DECIMAL_FRACTION_DIGITS
+                {
+                    return Err(Error::InvalidDecimal(number.to_string()));
+                }
+                number
+                    .parse()
+                    .map(BareItem::Decimal)
+                    .map_err(|_| Error::InvalidDecimal(number.to_string()))
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<BareItem> {
+        self.expect(b'"')?;
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some(b'\\') => match self.next() {
+                    Some(c @ (b'"' | b'\\')) => value.push(char::from(c)),
+                    Some(c) => {
+                        self.pos -= 1;
+                        return Err(self.unexpected(c));
+                    }
+                    None => return Err(Error::UnexpectedEnd),
+                },
+                Some(b'"') => return Ok(BareItem::String(value)),
+                Some(c @ 0x20..=0x7e) => value.push(char::from(c)),
+                Some(c) => {
+                    self.pos -= 1;
+                    return Err(self.unexpected(c));
+                }
+                None => return Err(Error::UnexpectedEnd),
+            }
+        }
+    }
+
+    fn parse_token(&mut self) -> Result<BareItem> {
+        let start = self.pos;
+        self.pos += 1;
+        while let Some(c) = self.peek() {
+            if !(is_tchar(c) || c == b':' || c == b'/') {
+                break;
+            }
+            self.pos += 1;
+        }
+        Ok(BareItem::Token(self.slice(start).to_string()))
+    }
+
+    fn parse_byte_sequence(&mut self) -> Result<BareItem> {
+        self.expect(b':')?;
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c == b':' {
+                break;
+            }
+            if !(c.is_ascii_alphanumeric() || b"+/=".contains(&c)) {
+                return Err(self.unexpected(c));
+            }
+            self.pos += 1;
+        }
+        let encoded = self.slice(start).to_string();
+        self.expect(b':')?;
+        BASE64
+            .decode(&encoded)
+            .map(BareItem::ByteSequence)
+            .map_err(|_| Error::InvalidByteSequence(encoded))
+    }
+
+    fn parse_boolean(&mut self) -> Result<BareItem> {
+        self.expect(b'?')?;
+        match self.next() {
+            Some(b'1') => Ok(BareItem::Boolean(true)),
+            Some(b'0') => Ok(BareItem::Boolean(false)),
+            Some(c) => {
+                self.pos -= 1;
+                Err(self.unexpected(c))
+            }
+            None => Err(Error::UnexpectedEnd),
+        }
+    }
+
+    /// Get the input from `start` up to the current position.
+    /// All callers only advance over ASCII characters.
+    fn slice(&self, start: usize) -> &'a str {
+        std::str::from_utf8(&self.input[start..self.pos]).unwrap_or_default()
+    }
+}
+
+/// Token characters as defined in RFC 9110
+const fn is_tchar(c: u8) -> bool {
+    c.is_ascii_alphanumeric()
+        || matches!(
+            c,
+            b'!' | b'#'
+                | b'$'
+                | b'%'
+                | b'&'
+                | b'\''
+                | b'*'
+                | b'+'
+                | b'-'
+                | b'.'
+                | b'^'
+                | b'_'
+                | b'`'
+                | b'|'
+                | b'~'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_bare_items() {
+        assert_eq!(parse_item("42").unwrap().bare_item, BareItem::Integer(42));
+        assert_eq!(parse_item("-7").unwrap().bare_item, BareItem::Integer(-7));
+        assert_eq!(parse_item("4.5").unwrap().bare_item, BareItem::Decimal(4.5));
+        assert_eq!(
+            parse_item(r#""a \"quoted\" \\ string""#).unwrap().bare_item,
+            BareItem::String(r#"a "quoted" \ string"#.to_string())
+        );
+        assert_eq!(
+            parse_item("foo/bar:baz").unwrap().bare_item,
+            BareItem::Token("foo/bar:baz".to_string())
+        );
+        assert_eq!(
+            parse_item(":cHJldGVuZA==:").unwrap().bare_item,
+            BareItem::ByteSequence(b"pretend".to_vec())
+        );
+        assert_eq!(
+            parse_item("?0").unwrap().bare_item,
+            BareItem::Boolean(false)
+        );
+    }
+
+    #[test]
+    fn parse_invalid_items() {
+        assert!(matches!(parse_item(""), Err(Error::UnexpectedEnd)));
+        assert!(matches!(
+            parse_item("1234567890123456"),
+            Err(Error::InvalidInteger(_))
+        ));
+        assert!(matches!(
+            parse_item("1.2345"),
+            Err(Error::InvalidDecimal(_))
+        ));
+        assert!(matches!(parse_item("1."), Err(Error::InvalidDecimal(_))));
+        assert!(matches!(parse_item(r#""open"#), Err(Error::UnexpectedEnd)));
+        assert!(matches!(
+            parse_item(":not base64!:"),
+            Err(Error::UnexpectedCharacter(' ', 4))
+        ));
+        assert!(matches!(
+            parse_item("0 foo"),
+            Err(Error::UnexpectedCharacter('f', 2))
+        ));
+        assert!(matches!(
+            parse_item("?2"),
+            Err(Error::UnexpectedCharacter('2', 1))
+        ));
+    }
+
+    #[test]
+    fn parse_item_parameters() {
+        let item = parse_item("100;w=60;flag;name=\"x\"").unwrap();
+        assert_eq!(item.bare_item, BareItem::Integer(100));
+        assert_eq!(item.params.len(), 3);
+        assert_eq!(item.params.get("w"), Some(&BareItem::Integer(60)));
+        assert_eq!(item.params.get("flag"), Some(&BareItem::Boolean(true)));
+        assert_eq!(
+            item.params.get("name").and_then(BareItem::as_str),
+            Some("x")
+        );
+        assert!(matches!(
+            parse_item("100;W=60"),
+            Err(Error::UnexpectedCharacter('W', 4))
+        ));
+    }
+
+    #[test]
+    fn parse_lists() {
+        let list = parse_list("  10, 10;w=1,\t1000;w=3600, (1 2);a  ").unwrap();
+        assert_eq!(list.len(), 4);
+        assert_eq!(list[0].as_item().unwrap().bare_item, BareItem::Integer(10));
+        assert_eq!(list[2].params().get("w"), Some(&BareItem::Integer(3600)));
+        assert_eq!(
+            list[3],
+            ListEntry::InnerList(InnerList {
+                items: vec![
+                    Item {
+                        bare_item: BareItem::Integer(1),
+                        params: Parameters::default()
+                    },
+                    Item {
+                        bare_item: BareItem::Integer(2),
+                        params: Parameters::default()
+                    }
+                ],
+                params: Parameters(vec![("a".to_string(), BareItem::Boolean(true))]),
+            })
+        );
+
+        assert!(parse_list("").unwrap().is_empty());
+        assert!(matches!(parse_list("1,"), Err(Error::UnexpectedEnd)));
+        assert!(matches!(
+            parse_list("1,,2"),
+            Err(Error::UnexpectedCharacter(',', 2))
+        ));
+    }
+
+    #[test]
+    fn parse_dictionaries() {
+        let dict = parse_dictionary("a=1, b;x=?0, c=(1 2), a=3").unwrap();
+        assert_eq!(dict.len(), 3);
+        assert_eq!(
+            dict.iter().map(|(k, _)| k).collect::<Vec<_>>(),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(
+            dict.get("a")
+                .and_then(ListEntry::as_item)
+                .unwrap()
+                .bare_item,
+            BareItem::Integer(3)
+        );
+        let b = dict.get("b").and_then(ListEntry::as_item).unwrap();
+        assert_eq!(b.bare_item, BareItem::Boolean(true));
+        assert_eq!(b.params.get("x"), Some(&BareItem::Boolean(false)));
+
+        assert!(matches!(
+            parse_dictionary("A=1"),
+            Err(Error::UnexpectedCharacter('A', 0))
+        ));
+    }
+
+    #[test]
+    fn parse_list_members() {
+        let list = parse_list(r#""burst";q=100;pk=:cHJldGVuZA==:, daily;flag;w=?0"#).unwrap();
+        let burst = list[0].as_item().unwrap();
+        assert_eq!(burst.bare_item, BareItem::String("burst".to_string()));
+        assert_eq!(burst.params.get("q"), Some(&BareItem::Integer(100)));
+        assert_eq!(
+            burst.params.get("pk").and_then(BareItem::as_bytes),
+            Some(&b"pretend"[..])
+        );
+        let daily = list[1].as_item().unwrap();
+        assert_eq!(daily.bare_item.as_str(), Some("daily"));
+        assert_eq!(daily.params.get("flag"), Some(&BareItem::Boolean(true)));
+        assert_eq!(daily.params.get("w"), Some(&BareItem::Boolean(false)));
+
+        assert!(matches!(
+            parse_list("1234567890123456"),
+            Err(Error::InvalidInteger(_))
+        ));
+        assert!(matches!(
+            parse_list("1.2345"),
+            Err(Error::InvalidDecimal(_))
+        ));
+        assert!(matches!(parse_list(r#""open"#), Err(Error::UnexpectedEnd)));
+        assert!(matches!(
+            parse_list("burst;W=60"),
+            Err(Error::UnexpectedCharacter('W', 6))
+        ));
+    }
+}
@@ -1,11 +1,9 @@
 #[cfg(test)]
 mod cli {
     use http::header::HeaderMap;
-    use rate_limits::{RateLimit, ResetTime, Vendor};
+    use rate_limits::{RateLimit, ResetTime, ResetTimeKind, Vendor};
     use time::{Duration, OffsetDateTime};
 
-    use rate_limits::headers;
-
     #[test]
     fn test_example() {
         let mut headers = HeaderMap::new();
@@ -13,17 +11,24 @@ mod cli {
         headers.insert("X-RATELIMIT-REMAINING", "4987".parse().unwrap());
         headers.insert("X-RATELIMIT-RESET", "1350085394".parse().unwrap());
 
+        let rate = RateLimit::new(headers).unwrap();
+        assert_eq!(rate.limit(), Some(5000));
+        assert_eq!(rate.remaining(), Some(4987));
         assert_eq!(
-            RateLimit::new(headers).unwrap(),
-            RateLimit::Rfc6585(headers::Headers {
-                limit: 5000,
-                remaining: 4987,
-                reset: ResetTime::DateTime(
-                    OffsetDateTime::from_unix_timestamp(1350085394).unwrap()
-                ),
-                window: Some(Duration::HOUR),
-                vendor: Vendor::Github
-            }),
+            rate.reset(),
+            Some(ResetTime::DateTime(
+                OffsetDateTime::from_unix_timestamp(1350085394).unwrap()
+            ))
         );
+
+        let RateLimit::Rfc6585(rate) = rate else {
+            panic!("expected vendor headers")
+        };
+        assert_eq!(rate.vendor, Vendor::Github);
+        assert_eq!(rate.policies(), &[]);
+        assert_eq!(rate.window, Some(Duration::HOUR));
+        assert_eq!(rate.reset_kind(), Some(ResetTimeKind::Timestamp));
+        assert_eq!(rate.confidence(), 1.0);
+        assert!(rate.warnings().is_empty());
     }
 }